@@ -0,0 +1,94 @@
+use crate::air_quality::AirQualityStatus;
+
+pub const MSP_PAYLOAD_LEN: usize = 4;
+
+// 载荷格式版本
+const VERSION: u8 = 0x01;
+// flags 中表示空气质量已知
+const AIR_QUALITY_VALID: u8 = 0x80;
+// flags 中表示即将需要通风
+const VENTILATION_SOON: u8 = 0x08;
+// 剩余分钟数不超过该值时设置 VENTILATION_SOON
+const SOON_MINUTES: u16 = 15;
+// 没有通风预测时的分钟数
+const NOT_DUE: u8 = 0xFF;
+// 广播中的分钟数按 5 分钟取整，避免每次读数都要更新广播
+const MINUTES_STEP: u16 = 5;
+
+/// Device status carried in the manufacturer specific data, so scanners can
+/// read it without connecting.
+///
+/// Layout: `[version, flags, minutes, reserved]`, flags bits 0-2 are the
+/// air quality flags, bit 3 means ventilation is due within 15 minutes and
+/// bit 7 marks the air quality as valid. `minutes` is the predicted time until
+/// ventilation is due rounded up to 5 minutes, up to 250, 0xFF when none is
+/// predicted.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdvStatus {
+    pub air_quality: Option<AirQualityStatus>,
+    pub ventilation_due: Option<u16>,
+}
+
+impl AdvStatus {
+    pub fn payload(&self) -> [u8; MSP_PAYLOAD_LEN] {
+        let flags = match self.air_quality {
+            Some(status) => status.flags() | AIR_QUALITY_VALID,
+            None => 0,
+        };
+        let (flags, minutes) = match self.ventilation_due {
+            Some(minutes) => {
+                let flags = if minutes <= SOON_MINUTES {
+                    flags | VENTILATION_SOON
+                } else {
+                    flags
+                };
+                let rounded = minutes.div_ceil(MINUTES_STEP) * MINUTES_STEP;
+                (flags, rounded.min(250) as u8)
+            }
+            None => (flags, NOT_DUE),
+        };
+        [VERSION, flags, minutes, 0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air_quality::AirQuality;
+
+    const POOR_ALARM: AirQualityStatus = AirQualityStatus {
+        quality: AirQuality::Poor,
+        alarm: true,
+    };
+
+    #[test]
+    fn unknown_status() {
+        assert_eq!(AdvStatus::default().payload(), [VERSION, 0, NOT_DUE, 0]);
+    }
+
+    #[test]
+    fn air_quality_flags() {
+        let status = AdvStatus {
+            air_quality: Some(POOR_ALARM),
+            ventilation_due: None,
+        };
+        assert_eq!(status.payload(), [VERSION, 0x86, NOT_DUE, 0]);
+    }
+
+    #[test]
+    fn ventilation_minutes_round_up() {
+        let payload = |minutes| {
+            AdvStatus {
+                air_quality: None,
+                ventilation_due: Some(minutes),
+            }
+            .payload()
+        };
+        assert_eq!(payload(0), [VERSION, VENTILATION_SOON, 0, 0]);
+        assert_eq!(payload(11), [VERSION, VENTILATION_SOON, 15, 0]);
+        assert_eq!(payload(16), [VERSION, 0, 20, 0]);
+        // 超过 250 分钟时封顶，不与 NOT_DUE 混淆
+        assert_eq!(payload(1000), [VERSION, 0, 250, 0]);
+    }
+}
@@ -0,0 +1,224 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AirQuality {
+    Good,
+    Moderate,
+    Poor,
+    Unhealthy,
+}
+
+impl AirQuality {
+    const fn from_index(index: usize) -> Self {
+        match index {
+            0 => Self::Good,
+            1 => Self::Moderate,
+            2 => Self::Poor,
+            _ => Self::Unhealthy,
+        }
+    }
+}
+
+/// Lower bounds (ppm) of each band above `Good`, plus the alarm level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AirQualityConfig {
+    pub moderate_ppm: u16,
+    pub poor_ppm: u16,
+    pub unhealthy_ppm: u16,
+    pub alarm_ppm: u16,
+    /// 下降时需要低于阈值多少才切换到更好的等级
+    pub hysteresis_ppm: u16,
+}
+
+impl Default for AirQualityConfig {
+    fn default() -> Self {
+        Self {
+            moderate_ppm: 800,
+            poor_ppm: 1000,
+            unhealthy_ppm: 1400,
+            alarm_ppm: 2000,
+            hysteresis_ppm: 50,
+        }
+    }
+}
+
+impl AirQualityConfig {
+    /// Thresholds must be strictly increasing
+    pub fn is_valid(&self) -> bool {
+        self.moderate_ppm < self.poor_ppm
+            && self.poor_ppm < self.unhealthy_ppm
+            && self.unhealthy_ppm <= self.alarm_ppm
+    }
+
+    fn band(&self, co2_ppm: u16) -> AirQuality {
+        let index = [self.moderate_ppm, self.poor_ppm, self.unhealthy_ppm]
+            .iter()
+            .filter(|&&threshold| co2_ppm >= threshold)
+            .count();
+        AirQuality::from_index(index)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AirQualityStatus {
+    pub quality: AirQuality,
+    pub alarm: bool,
+}
+
+impl AirQualityStatus {
+    const ALARM: u8 = 0b100;
+
+    /// Bits 0-1 hold the band, bit 2 the alarm
+    pub const fn flags(&self) -> u8 {
+        self.quality as u8 | if self.alarm { Self::ALARM } else { 0 }
+    }
+
+    /// Lit rows of the LED bar: one per band, the fifth row for the alarm
+    pub const fn bar_level(&self) -> usize {
+        self.quality as usize + 1 + self.alarm as usize
+    }
+}
+
+pub struct Classifier {
+    config: AirQualityConfig,
+    status: AirQualityStatus,
+}
+
+impl Classifier {
+    pub const fn new(config: AirQualityConfig) -> Self {
+        Self {
+            config,
+            status: AirQualityStatus {
+                quality: AirQuality::Good,
+                alarm: false,
+            },
+        }
+    }
+
+    pub const fn config(&self) -> AirQualityConfig {
+        self.config
+    }
+
+    pub fn configure(&mut self, config: AirQualityConfig) {
+        self.config = config;
+    }
+
+    pub const fn status(&self) -> AirQualityStatus {
+        self.status
+    }
+
+    /// Classify a reading. Rising into a worse band is immediate, falling back
+    /// requires the reading to drop `hysteresis_ppm` below the band's threshold.
+    pub fn update(&mut self, co2_ppm: u16) -> AirQualityStatus {
+        let hysteresis = self.config.hysteresis_ppm;
+        let rising = self.config.band(co2_ppm);
+        let falling = self.config.band(co2_ppm.saturating_add(hysteresis));
+        if rising > self.status.quality {
+            self.status.quality = rising;
+        } else if falling < self.status.quality {
+            self.status.quality = falling;
+        }
+
+        if co2_ppm >= self.config.alarm_ppm {
+            self.status.alarm = true;
+        } else if co2_ppm.saturating_add(hysteresis) < self.config.alarm_ppm {
+            self.status.alarm = false;
+        }
+        self.status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_at_default_thresholds() {
+        let config = AirQualityConfig::default();
+        assert!(config.is_valid());
+        for (ppm, quality) in [
+            (420, AirQuality::Good),
+            (799, AirQuality::Good),
+            (800, AirQuality::Moderate),
+            (1000, AirQuality::Poor),
+            (1400, AirQuality::Unhealthy),
+            (5000, AirQuality::Unhealthy),
+        ] {
+            assert_eq!(
+                Classifier::new(config).update(ppm).quality,
+                quality,
+                "{ppm} ppm"
+            );
+        }
+    }
+
+    #[test]
+    fn rises_immediately_and_falls_with_hysteresis() {
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        assert_eq!(classifier.update(1000).quality, AirQuality::Poor);
+        // 低于阈值但仍在回差范围内
+        assert_eq!(classifier.update(980).quality, AirQuality::Poor);
+        assert_eq!(classifier.update(950).quality, AirQuality::Poor);
+        assert_eq!(classifier.update(949).quality, AirQuality::Moderate);
+        // 一次下降可以跨越多个等级
+        assert_eq!(classifier.update(1500).quality, AirQuality::Unhealthy);
+        assert_eq!(classifier.update(500).quality, AirQuality::Good);
+    }
+
+    #[test]
+    fn alarm_latches_until_below_hysteresis() {
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        assert!(!classifier.update(1999).alarm);
+        assert!(classifier.update(2000).alarm);
+        assert!(classifier.update(1951).alarm);
+        assert!(!classifier.update(1949).alarm);
+    }
+
+    #[test]
+    fn configure_keeps_status_until_next_reading() {
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        classifier.update(900);
+        let config = AirQualityConfig {
+            moderate_ppm: 1000,
+            poor_ppm: 1200,
+            unhealthy_ppm: 1500,
+            alarm_ppm: 1500,
+            hysteresis_ppm: 0,
+        };
+        classifier.configure(config);
+        assert_eq!(classifier.config(), config);
+        assert_eq!(classifier.status().quality, AirQuality::Moderate);
+        assert_eq!(classifier.update(900).quality, AirQuality::Good);
+    }
+
+    #[test]
+    fn rejects_unordered_thresholds() {
+        let config = AirQualityConfig {
+            poor_ppm: 800,
+            ..AirQualityConfig::default()
+        };
+        assert!(!config.is_valid());
+        let config = AirQualityConfig {
+            alarm_ppm: 1399,
+            ..AirQualityConfig::default()
+        };
+        assert!(!config.is_valid());
+    }
+
+    #[test]
+    fn flags_and_bar_level() {
+        let status = AirQualityStatus {
+            quality: AirQuality::Poor,
+            alarm: false,
+        };
+        assert_eq!(status.flags(), 0b010);
+        assert_eq!(status.bar_level(), 3);
+        let status = AirQualityStatus {
+            quality: AirQuality::Unhealthy,
+            alarm: true,
+        };
+        assert_eq!(status.flags(), 0b111);
+        assert_eq!(status.bar_level(), 5);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::air_quality::{AirQuality, AirQualityStatus};
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AlarmConfig {
+    /// 静音时不会响，但状态仍然上报
+    pub muted: bool,
+    pub frequency_hz: u16,
+    pub on_ms: u16,
+    pub off_ms: u16,
+    pub snooze_min: u8,
+    /// Quiet hours as minutes since midnight, equal start and end disables them
+    pub quiet_start_min: u16,
+    pub quiet_end_min: u16,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            muted: false,
+            frequency_hz: 2000,
+            on_ms: 200,
+            off_ms: 800,
+            snooze_min: 10,
+            quiet_start_min: 22 * 60,
+            quiet_end_min: 7 * 60,
+        }
+    }
+}
+
+impl AlarmConfig {
+    /// Whether `minute_of_day` falls into the quiet hours, which may wrap past midnight
+    pub fn is_quiet(&self, minute_of_day: u16) -> bool {
+        let minute = minute_of_day % MINUTES_PER_DAY;
+        let (start, end) = (self.quiet_start_min, self.quiet_end_min);
+        if start <= end {
+            (start..end).contains(&minute)
+        } else {
+            minute >= start || minute < end
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlarmState {
+    Idle,
+    Sounding,
+    Snoozed,
+    /// Alarm condition present but silenced by mute or quiet hours
+    Silenced,
+}
+
+pub struct Alarm {
+    config: AlarmConfig,
+    triggered: bool,
+    snoozed_until_ms: Option<u64>,
+    state: AlarmState,
+}
+
+impl Alarm {
+    pub const fn new(config: AlarmConfig) -> Self {
+        Self {
+            config,
+            triggered: false,
+            snoozed_until_ms: None,
+            state: AlarmState::Idle,
+        }
+    }
+
+    pub const fn config(&self) -> AlarmConfig {
+        self.config
+    }
+
+    pub fn configure(&mut self, config: AlarmConfig) {
+        self.config = config;
+    }
+
+    pub const fn state(&self) -> AlarmState {
+        self.state
+    }
+
+    /// Set whether the alarm condition is present
+    pub fn trigger(&mut self, triggered: bool) {
+        if !triggered {
+            // 空气恢复后清除贪睡，下次超标立即提醒
+            self.snoozed_until_ms = None;
+        }
+        self.triggered = triggered;
+    }
+
+    /// Trigger while CO2 is in the poor band or worse, with the classifier's hysteresis
+    pub fn observe(&mut self, status: AirQualityStatus) {
+        self.trigger(status.quality >= AirQuality::Poor);
+    }
+
+    /// Snooze a sounding alarm, returns false if there was nothing to snooze
+    pub fn snooze(&mut self, now_ms: u64) -> bool {
+        if self.state != AlarmState::Sounding {
+            return false;
+        }
+        self.snoozed_until_ms = Some(now_ms + self.config.snooze_min as u64 * 60_000);
+        self.state = AlarmState::Snoozed;
+        true
+    }
+
+    /// Re-evaluate the state; `minute_of_day` is `None` while the clock is unset
+    pub fn update(&mut self, now_ms: u64, minute_of_day: Option<u16>) -> AlarmState {
+        if self
+            .snoozed_until_ms
+            .is_some_and(|until_ms| now_ms >= until_ms)
+        {
+            self.snoozed_until_ms = None;
+        }
+
+        let quiet = minute_of_day.is_some_and(|minute| self.config.is_quiet(minute));
+        self.state = if !self.triggered {
+            AlarmState::Idle
+        } else if self.snoozed_until_ms.is_some() {
+            AlarmState::Snoozed
+        } else if self.config.muted || quiet {
+            AlarmState::Silenced
+        } else {
+            AlarmState::Sounding
+        };
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air_quality::{AirQualityConfig, Classifier};
+
+    const fn at(hours: u16, minutes: u16) -> u16 {
+        hours * 60 + minutes
+    }
+
+    fn sounding() -> Alarm {
+        let mut alarm = Alarm::new(AlarmConfig::default());
+        alarm.trigger(true);
+        assert_eq!(alarm.update(0, None), AlarmState::Sounding);
+        alarm
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let config = AlarmConfig::default();
+        assert!(!config.is_quiet(at(21, 59)));
+        assert!(config.is_quiet(at(22, 0)));
+        assert!(config.is_quiet(at(23, 59)));
+        assert!(config.is_quiet(at(0, 0)));
+        assert!(config.is_quiet(at(6, 59)));
+        assert!(!config.is_quiet(at(7, 0)));
+        assert!(!config.is_quiet(at(12, 0)));
+        // 超过一天的分钟数按一天取模
+        assert!(config.is_quiet(MINUTES_PER_DAY + at(1, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let config = AlarmConfig {
+            quiet_start_min: at(13, 0),
+            quiet_end_min: at(14, 30),
+            ..AlarmConfig::default()
+        };
+        assert!(!config.is_quiet(at(12, 59)));
+        assert!(config.is_quiet(at(13, 0)));
+        assert!(!config.is_quiet(at(14, 30)));
+        assert!(!config.is_quiet(at(23, 0)));
+    }
+
+    #[test]
+    fn equal_quiet_bounds_disable_them() {
+        let config = AlarmConfig {
+            quiet_start_min: at(8, 0),
+            quiet_end_min: at(8, 0),
+            ..AlarmConfig::default()
+        };
+        assert!((0..MINUTES_PER_DAY).all(|minute| !config.is_quiet(minute)));
+    }
+
+    #[test]
+    fn idle_until_triggered() {
+        let mut alarm = Alarm::new(AlarmConfig::default());
+        assert_eq!(alarm.update(0, Some(at(12, 0))), AlarmState::Idle);
+        alarm.trigger(true);
+        assert_eq!(alarm.update(0, Some(at(12, 0))), AlarmState::Sounding);
+        alarm.trigger(false);
+        assert_eq!(alarm.update(0, Some(at(12, 0))), AlarmState::Idle);
+    }
+
+    #[test]
+    fn mute_and_quiet_hours_silence() {
+        let mut alarm = Alarm::new(AlarmConfig {
+            muted: true,
+            ..AlarmConfig::default()
+        });
+        alarm.trigger(true);
+        assert_eq!(alarm.update(0, Some(at(12, 0))), AlarmState::Silenced);
+
+        alarm.configure(AlarmConfig::default());
+        assert_eq!(alarm.update(0, Some(at(23, 0))), AlarmState::Silenced);
+        assert_eq!(alarm.update(0, Some(at(7, 0))), AlarmState::Sounding);
+        // 时钟未设置时不知道是否在静音时段
+        assert_eq!(alarm.update(0, None), AlarmState::Sounding);
+    }
+
+    #[test]
+    fn snooze_expires() {
+        let mut alarm = sounding();
+        assert!(alarm.snooze(1000));
+        assert_eq!(alarm.update(1000, None), AlarmState::Snoozed);
+        // 默认贪睡 10 分钟
+        assert_eq!(alarm.update(1000 + 599_999, None), AlarmState::Snoozed);
+        assert_eq!(alarm.update(1000 + 600_000, None), AlarmState::Sounding);
+    }
+
+    #[test]
+    fn snooze_needs_a_sounding_alarm() {
+        let mut alarm = Alarm::new(AlarmConfig::default());
+        assert!(!alarm.snooze(0));
+        let mut alarm = sounding();
+        assert!(alarm.snooze(0));
+        // 已经贪睡时再按不消耗
+        assert!(!alarm.snooze(0));
+    }
+
+    #[test]
+    fn recovery_clears_snooze() {
+        let mut alarm = sounding();
+        alarm.snooze(0);
+        alarm.trigger(false);
+        assert_eq!(alarm.update(1000, None), AlarmState::Idle);
+        // 再次超标立即提醒
+        alarm.trigger(true);
+        assert_eq!(alarm.update(2000, None), AlarmState::Sounding);
+    }
+
+    #[test]
+    fn sounds_from_the_poor_band() {
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        let mut alarm = Alarm::new(AlarmConfig::default());
+        for (co2, expected) in [
+            (999, AlarmState::Idle),
+            (1000, AlarmState::Sounding),
+            // 回差内保持
+            (960, AlarmState::Sounding),
+            (1500, AlarmState::Sounding),
+            (949, AlarmState::Idle),
+        ] {
+            alarm.observe(classifier.update(co2));
+            assert_eq!(alarm.update(0, None), expected, "{co2} ppm");
+        }
+    }
+}
@@ -0,0 +1,233 @@
+pub const BUTTON_A: u8 = 0b01;
+pub const BUTTON_B: u8 = 0b10;
+
+// 连续多少次采样一致才认为状态稳定
+const DEBOUNCE_SAMPLES: u8 = 3;
+const LONG_PRESS_MS: u32 = 1500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Gesture {
+    Short(u8),
+    Long(u8),
+}
+
+#[derive(Default)]
+pub struct Debouncer {
+    stable: u8,
+    candidate: u8,
+    samples: u8,
+    // 本次按下期间出现过的按键
+    combo: u8,
+    pressed_at_ms: u32,
+    long_fired: bool,
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Update {
+    pub state: Option<u8>,
+    pub gesture: Option<Gesture>,
+}
+
+impl Debouncer {
+    pub fn is_idle(&self) -> bool {
+        self.stable == 0 && self.candidate == 0
+    }
+
+    /// Feed one raw sample (pressed buttons as bits) taken at `now_ms`
+    pub fn update(&mut self, raw: u8, now_ms: u32) -> Update {
+        let mut update = Update::default();
+
+        if raw != self.candidate {
+            self.candidate = raw;
+            self.samples = 1;
+        } else if self.samples < DEBOUNCE_SAMPLES {
+            self.samples += 1;
+        }
+
+        if self.samples == DEBOUNCE_SAMPLES && self.candidate != self.stable {
+            if self.stable == 0 {
+                self.pressed_at_ms = now_ms;
+                self.combo = 0;
+                self.long_fired = false;
+            }
+            self.stable = self.candidate;
+            self.combo |= self.stable;
+            update.state = Some(self.stable);
+
+            if self.stable == 0 && !self.long_fired {
+                update.gesture = Some(Gesture::Short(self.combo));
+            }
+        }
+
+        if self.stable != 0
+            && !self.long_fired
+            && now_ms.wrapping_sub(self.pressed_at_ms) >= LONG_PRESS_MS
+        {
+            self.long_fired = true;
+            update.gesture = Some(Gesture::Long(self.combo));
+        }
+
+        update
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Confirmation {
+    /// Calibration was requested, ask for confirmation
+    Prompt,
+    Calibrate,
+    /// A different gesture arrived while waiting, it is consumed
+    Cancelled,
+    /// Not part of the calibration sequence, handle the gesture as usual
+    Pass,
+}
+
+/// Forced recalibration needs a long press of A and B together, then a short
+/// press of A within `TIMEOUT_MS`. Any other gesture cancels it, so a stray
+/// long press can't calibrate the sensor against indoor air.
+#[derive(Default)]
+pub struct CalibrationConfirm {
+    requested_at_ms: Option<u32>,
+}
+
+impl CalibrationConfirm {
+    pub const REQUEST: Gesture = Gesture::Long(BUTTON_A | BUTTON_B);
+    pub const CONFIRM: Gesture = Gesture::Short(BUTTON_A);
+    pub const TIMEOUT_MS: u32 = 5000;
+
+    pub fn update(&mut self, gesture: Gesture, now_ms: u32) -> Confirmation {
+        let pending = self
+            .requested_at_ms
+            .take()
+            .is_some_and(|at| now_ms.wrapping_sub(at) < Self::TIMEOUT_MS);
+        match gesture {
+            Self::CONFIRM if pending => Confirmation::Calibrate,
+            _ if pending => Confirmation::Cancelled,
+            Self::REQUEST => {
+                self.requested_at_ms = Some(now_ms);
+                Confirmation::Prompt
+            }
+            _ => Confirmation::Pass,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每 10 ms 采样一次，返回期间产生的手势
+    fn press(debouncer: &mut Debouncer, raw: u8, from_ms: u32, to_ms: u32) -> Option<Gesture> {
+        (from_ms..to_ms)
+            .step_by(10)
+            .filter_map(|t| debouncer.update(raw, t).gesture)
+            .last()
+    }
+
+    #[test]
+    fn short_and_long_presses() {
+        let mut debouncer = Debouncer::default();
+        assert!(debouncer.is_idle());
+        assert_eq!(press(&mut debouncer, BUTTON_A, 0, 200), None);
+        assert_eq!(
+            press(&mut debouncer, 0, 200, 300),
+            Some(Gesture::Short(BUTTON_A))
+        );
+        assert!(debouncer.is_idle());
+
+        assert_eq!(
+            press(&mut debouncer, BUTTON_B, 1000, 3000),
+            Some(Gesture::Long(BUTTON_B))
+        );
+        // 长按之后松开不再产生短按
+        assert_eq!(press(&mut debouncer, 0, 3000, 3100), None);
+    }
+
+    #[test]
+    fn bounces_are_ignored() {
+        let mut debouncer = Debouncer::default();
+        let mut t = 0;
+        for raw in [BUTTON_A, 0, BUTTON_A, 0, 0, 0] {
+            assert_eq!(debouncer.update(raw, t), Update::default());
+            t += 10;
+        }
+        assert!(debouncer.is_idle());
+    }
+
+    #[test]
+    fn combination_is_remembered() {
+        let mut debouncer = Debouncer::default();
+        press(&mut debouncer, BUTTON_A, 0, 100);
+        assert_eq!(
+            press(&mut debouncer, BUTTON_A | BUTTON_B, 100, 2000),
+            Some(Gesture::Long(BUTTON_A | BUTTON_B))
+        );
+        let mut debouncer = Debouncer::default();
+        press(&mut debouncer, BUTTON_A | BUTTON_B, 0, 100);
+        press(&mut debouncer, BUTTON_B, 100, 200);
+        assert_eq!(
+            press(&mut debouncer, 0, 200, 300),
+            Some(Gesture::Short(BUTTON_A | BUTTON_B))
+        );
+    }
+
+    #[test]
+    fn calibration_needs_confirmation() {
+        let mut confirm = CalibrationConfirm::default();
+        assert_eq!(
+            confirm.update(CalibrationConfirm::REQUEST, 0),
+            Confirmation::Prompt
+        );
+        assert_eq!(
+            confirm.update(CalibrationConfirm::CONFIRM, 4999),
+            Confirmation::Calibrate
+        );
+        // 确认只生效一次
+        assert_eq!(
+            confirm.update(CalibrationConfirm::CONFIRM, 5000),
+            Confirmation::Pass
+        );
+    }
+
+    #[test]
+    fn other_long_presses_do_not_request() {
+        let mut confirm = CalibrationConfirm::default();
+        for gesture in [Gesture::Long(BUTTON_A), Gesture::Long(BUTTON_B)] {
+            assert_eq!(confirm.update(gesture, 0), Confirmation::Pass);
+            assert_eq!(
+                confirm.update(CalibrationConfirm::CONFIRM, 100),
+                Confirmation::Pass
+            );
+        }
+    }
+
+    #[test]
+    fn timeout_or_other_gesture_cancels() {
+        let mut confirm = CalibrationConfirm::default();
+        confirm.update(CalibrationConfirm::REQUEST, 0);
+        assert_eq!(
+            confirm.update(CalibrationConfirm::CONFIRM, 5000),
+            Confirmation::Pass
+        );
+
+        confirm.update(CalibrationConfirm::REQUEST, 10_000);
+        assert_eq!(
+            confirm.update(Gesture::Short(BUTTON_B), 11_000),
+            Confirmation::Cancelled
+        );
+        assert_eq!(
+            confirm.update(CalibrationConfirm::CONFIRM, 11_500),
+            Confirmation::Pass
+        );
+
+        // 重复长按 A+B 也会取消，而不是当作确认
+        confirm.update(CalibrationConfirm::REQUEST, 20_000);
+        assert_eq!(
+            confirm.update(CalibrationConfirm::REQUEST, 21_000),
+            Confirmation::Cancelled
+        );
+    }
+}
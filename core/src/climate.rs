@@ -0,0 +1,165 @@
+// Magnus 公式系数，-45 °C 到 60 °C 内误差约 0.35 °C
+const MAGNUS_B: f32 = 17.62;
+const MAGNUS_C: f32 = 243.12;
+
+/// Comfort class from temperature, humidity and dew point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Comfort {
+    Cold,
+    Dry,
+    Comfortable,
+    /// Dew point high enough to feel muggy
+    Humid,
+    /// Heat index in the NOAA caution range or above
+    Hot,
+}
+
+/// Values derived from a temperature and relative humidity reading
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Climate {
+    pub dew_point_c: f32,
+    pub absolute_humidity_g_m3: f32,
+    pub heat_index_c: f32,
+    pub comfort: Comfort,
+}
+
+impl Climate {
+    // 低于该温度视为偏冷
+    const COLD_C: f32 = 18.0;
+    // 低于该相对湿度视为干燥
+    const DRY_PERCENT: f32 = 30.0;
+    // 露点高于该值开始感到闷热
+    const HUMID_DEW_POINT_C: f32 = 16.0;
+    // NOAA 热指数 "caution" 下限，80 °F
+    const HOT_HEAT_INDEX_C: f32 = 26.7;
+
+    pub fn new(temperature_c: f32, humidity_percent: f32) -> Self {
+        let humidity_percent = humidity_percent.clamp(1.0, 100.0);
+        let dew_point_c = dew_point_c(temperature_c, humidity_percent);
+        let heat_index_c = heat_index_c(temperature_c, humidity_percent);
+        let comfort = if heat_index_c >= Self::HOT_HEAT_INDEX_C {
+            Comfort::Hot
+        } else if dew_point_c >= Self::HUMID_DEW_POINT_C {
+            Comfort::Humid
+        } else if temperature_c < Self::COLD_C {
+            Comfort::Cold
+        } else if humidity_percent < Self::DRY_PERCENT {
+            Comfort::Dry
+        } else {
+            Comfort::Comfortable
+        };
+        Self {
+            dew_point_c,
+            absolute_humidity_g_m3: absolute_humidity_g_m3(temperature_c, humidity_percent),
+            heat_index_c,
+            comfort,
+        }
+    }
+}
+
+// 饱和水汽压 (hPa)
+fn saturation_pressure_hpa(temperature_c: f32) -> f32 {
+    6.112 * libm::expf(MAGNUS_B * temperature_c / (MAGNUS_C + temperature_c))
+}
+
+/// Dew point using the Magnus formula, `humidity_percent` must be above 0
+pub fn dew_point_c(temperature_c: f32, humidity_percent: f32) -> f32 {
+    let gamma = libm::logf(humidity_percent / 100.0)
+        + MAGNUS_B * temperature_c / (MAGNUS_C + temperature_c);
+    MAGNUS_C * gamma / (MAGNUS_B - gamma)
+}
+
+/// Mass of water vapour per volume of air
+pub fn absolute_humidity_g_m3(temperature_c: f32, humidity_percent: f32) -> f32 {
+    // 水汽的比气体常数换算：216.7 g·K/(hPa·m³)
+    let vapour_hpa = saturation_pressure_hpa(temperature_c) * humidity_percent / 100.0;
+    216.7 * vapour_hpa / (273.15 + temperature_c)
+}
+
+/// NOAA heat index: Steadman's simple formula, switching to the Rothfusz
+/// regression from 80 °F where the simple one underestimates
+pub fn heat_index_c(temperature_c: f32, humidity_percent: f32) -> f32 {
+    let t = temperature_c * 9.0 / 5.0 + 32.0;
+    let rh = humidity_percent;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let heat_index_f = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        -42.379 + 2.049_015_3 * t + 10.143_331 * rh
+            - 0.224_755_4 * t * rh
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * t * t * rh
+            + 0.000_852_82 * t * rh * rh
+            - 0.000_001_99 * t * t * rh * rh
+    };
+    (heat_index_f - 32.0) * 5.0 / 9.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn dew_point_known_values() {
+        // 饱和时露点等于气温
+        assert_close(dew_point_c(20.0, 100.0), 20.0, 0.01);
+        assert_close(dew_point_c(25.0, 50.0), 13.9, 0.1);
+        assert_close(dew_point_c(30.0, 80.0), 26.2, 0.1);
+        assert_close(dew_point_c(10.0, 40.0), -3.0, 0.1);
+    }
+
+    #[test]
+    fn absolute_humidity_known_values() {
+        assert_close(absolute_humidity_g_m3(20.0, 100.0), 17.3, 0.1);
+        assert_close(absolute_humidity_g_m3(25.0, 50.0), 11.5, 0.1);
+        assert_close(absolute_humidity_g_m3(0.0, 100.0), 4.85, 0.05);
+    }
+
+    #[test]
+    fn heat_index_matches_noaa_table() {
+        // NOAA 表格：90 °F、70% 时为 106 °F
+        assert_close(heat_index_c(32.22, 70.0), 41.1, 0.5);
+        // 100 °F、40% 时为 109 °F
+        assert_close(heat_index_c(37.78, 40.0), 42.8, 0.5);
+    }
+
+    #[test]
+    fn heat_index_is_close_to_temperature_when_mild() {
+        assert_close(heat_index_c(20.0, 50.0), 20.0, 1.0);
+        assert_close(heat_index_c(24.0, 40.0), 24.0, 1.0);
+    }
+
+    #[test]
+    fn comfort_classes() {
+        for (temperature, humidity, comfort) in [
+            (22.0, 45.0, Comfort::Comfortable),
+            (15.0, 60.0, Comfort::Cold),
+            (21.0, 20.0, Comfort::Dry),
+            (22.0, 75.0, Comfort::Humid),
+            (32.0, 70.0, Comfort::Hot),
+        ] {
+            assert_eq!(
+                Climate::new(temperature, humidity).comfort,
+                comfort,
+                "{temperature} °C {humidity}%"
+            );
+        }
+    }
+
+    #[test]
+    fn zero_humidity_gives_finite_values() {
+        let climate = Climate::new(21.0, 0.0);
+        assert!(climate.dew_point_c.is_finite());
+        assert!(climate.absolute_humidity_g_m3 >= 0.0);
+    }
+}
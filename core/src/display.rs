@@ -0,0 +1,7 @@
+pub mod font;
+pub mod led;
+pub mod mode;
+pub mod transform;
+
+/// 5x5 glyph, one byte per row from the top, bit 4 is the leftmost column
+pub type Glyph = [u8; 5];
@@ -0,0 +1,156 @@
+use super::Glyph;
+use crate::{climate::Comfort, trend::Trend};
+
+// 只包含显示读数需要的字符，其他字符显示为空白
+pub fn glyph(c: char) -> Glyph {
+    match c {
+        '0' => [0b01100, 0b10010, 0b10010, 0b10010, 0b01100],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b11100, 0b00010, 0b01100, 0b10000, 0b11110],
+        '3' => [0b11110, 0b00010, 0b00100, 0b10010, 0b01100],
+        '4' => [0b00110, 0b01010, 0b10010, 0b11111, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b11110],
+        '6' => [0b00010, 0b00100, 0b01110, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00010, 0b00100, 0b01000, 0b10000],
+        '8' => [0b01110, 0b10001, 0b01110, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b01110, 0b00100, 0b01000],
+        '-' => [0b00000, 0b00000, 0b01110, 0b00000, 0b00000],
+        '%' => [0b11001, 0b11010, 0b00100, 0b01011, 0b10011],
+        '?' => [0b01100, 0b10010, 0b00100, 0b00000, 0b00100],
+        'A' => [0b01100, 0b10010, 0b11110, 0b10010, 0b10010],
+        'C' => [0b01110, 0b10000, 0b10000, 0b10000, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10010, 0b10010, 0b11100],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b11110],
+        // 趋势箭头
+        '↑' => [0b00100, 0b01110, 0b10101, 0b00100, 0b00100],
+        '↓' => [0b00100, 0b00100, 0b10101, 0b01110, 0b00100],
+        '→' => [0b00100, 0b00010, 0b11111, 0b00010, 0b00100],
+        _ => [0; 5],
+    }
+}
+
+/// Arrow for the CO2 trend
+pub fn trend_arrow(trend: Trend) -> Glyph {
+    glyph(match trend {
+        Trend::Rising => '↑',
+        Trend::Stable => '→',
+        Trend::Falling => '↓',
+    })
+}
+
+/// Face for the comfort class: smiling when comfortable, flat when cold or
+/// dry, frowning when humid or hot
+pub fn comfort_face(comfort: Comfort) -> Glyph {
+    let mouth = match comfort {
+        Comfort::Comfortable => [0b10001, 0b01110],
+        Comfort::Cold | Comfort::Dry => [0b00000, 0b11111],
+        Comfort::Humid | Comfort::Hot => [0b01110, 0b10001],
+    };
+    [0b00000, 0b01010, 0b00000, mouth[0], mouth[1]]
+}
+
+// 每个字符 5 列，字符之间空 1 列
+const ADVANCE: usize = 6;
+
+/// Number of scroll steps for `text` to pass completely through the display
+pub fn scroll_len(text: &str) -> usize {
+    text.chars().count() * ADVANCE + 5
+}
+
+/// The 5x5 window of `text` laid out as a strip, starting `offset` columns
+/// before the strip so the text scrolls in from the right edge
+pub fn scroll_window(text: &str, offset: usize) -> Glyph {
+    let mut window = [0u8; 5];
+    for x in 0..5 {
+        // 窗口第 x 列对应文字条带中的列
+        let Some(column) = (offset + x).checked_sub(5) else {
+            continue;
+        };
+        let Some(c) = text.chars().nth(column / ADVANCE) else {
+            continue;
+        };
+        let glyph_x = column % ADVANCE;
+        if glyph_x == 5 {
+            continue;
+        }
+        let g = glyph(c);
+        for (row, bits) in window.iter_mut().enumerate() {
+            if g[row] & (0b10000 >> glyph_x) != 0 {
+                *bits |= 0b10000 >> x;
+            }
+        }
+    }
+    window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 把字形平移若干列，正数向右
+    fn shifted(glyph: Glyph, columns: i32) -> Glyph {
+        glyph.map(|row| {
+            let row = if columns >= 0 {
+                row >> columns
+            } else {
+                row << -columns
+            };
+            row & 0b11111
+        })
+    }
+
+    #[test]
+    fn unknown_characters_are_blank() {
+        assert_eq!(glyph('x'), [0; 5]);
+        assert_eq!(glyph(' '), [0; 5]);
+        assert_ne!(glyph('0'), [0; 5]);
+    }
+
+    #[test]
+    fn arrows_and_faces() {
+        assert_eq!(trend_arrow(Trend::Rising), glyph('↑'));
+        assert_eq!(trend_arrow(Trend::Stable), glyph('→'));
+        assert_eq!(trend_arrow(Trend::Falling), glyph('↓'));
+        assert_eq!(comfort_face(Comfort::Comfortable)[3..], [0b10001, 0b01110]);
+        assert_eq!(comfort_face(Comfort::Hot), comfort_face(Comfort::Humid));
+        assert_eq!(comfort_face(Comfort::Cold), comfort_face(Comfort::Dry));
+    }
+
+    #[test]
+    fn scroll_length_covers_text_and_display() {
+        assert_eq!(scroll_len(""), 5);
+        assert_eq!(scroll_len("1"), 11);
+        assert_eq!(scroll_len("1234"), 29);
+    }
+
+    #[test]
+    fn text_enters_from_the_right() {
+        let one = glyph('1');
+        assert_eq!(scroll_window("1", 0), [0; 5]);
+        for offset in 1..=5 {
+            assert_eq!(
+                scroll_window("1", offset),
+                shifted(one, 5 - offset as i32),
+                "{offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn characters_are_separated_by_a_blank_column() {
+        let (two, four) = (glyph('2'), glyph('4'));
+        // 窗口为 '2' 的后四列和间隔列
+        assert_eq!(scroll_window("24", 6), shifted(two, -1));
+        // 间隔列之后紧跟 '4' 的前四列
+        assert_eq!(scroll_window("24", 10), shifted(four, 1));
+        assert_eq!(scroll_window("24", 11), four);
+    }
+
+    #[test]
+    fn text_leaves_on_the_left() {
+        let text = "CO2";
+        let last = scroll_len(text) - 1;
+        assert_eq!(scroll_window(text, last), [0; 5]);
+        assert_eq!(scroll_window(text, last - 1), shifted(glyph('2'), -4));
+    }
+}
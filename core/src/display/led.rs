@@ -0,0 +1,298 @@
+pub const MAX_LEVEL: u8 = 10;
+
+const MODE_OFF: u8 = 0;
+const MODE_CONSTANT: u8 = 1;
+const MODE_BREATHE: u8 = 2;
+const MODE_ONE_SHOT: u8 = 3;
+
+// 呼吸灯单次渐亮或渐暗的时长
+const RAMP_MS: u32 = 1000;
+
+/// Preset colours of the breathe and one-shot modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LedColor {
+    Red = 1,
+    Green,
+    Yellow,
+    Blue,
+    Purple,
+    Cyan,
+    White,
+}
+
+impl TryFrom<u8> for LedColor {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Self::Red,
+            2 => Self::Green,
+            3 => Self::Yellow,
+            4 => Self::Blue,
+            5 => Self::Purple,
+            6 => Self::Cyan,
+            7 => Self::White,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// Why a TUS_LED write was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// Wrong number of parameter bytes for the mode
+    Length,
+    /// Unknown mode or colour
+    Value,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Led {
+    Off,
+    Constant {
+        red: u8,
+        green: u8,
+        blue: u8,
+    },
+    Breathe {
+        color: LedColor,
+        intensity: u8,
+        delay_ms: u16,
+    },
+    OneShot {
+        color: LedColor,
+        intensity: u8,
+    },
+}
+
+impl Led {
+    /// Parse a write to TUS_LED: mode byte followed by the mode parameters
+    pub fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        let (&mode, params) = data.split_first().ok_or(ParseError::Length)?;
+        let color = |value: u8| LedColor::try_from(value).map_err(|_| ParseError::Value);
+        match (mode, params) {
+            (MODE_OFF, []) => Ok(Self::Off),
+            (MODE_CONSTANT, &[red, green, blue]) => Ok(Self::Constant { red, green, blue }),
+            (MODE_BREATHE, &[color_id, intensity, delay_lo, delay_hi]) => Ok(Self::Breathe {
+                color: color(color_id)?,
+                intensity: intensity.clamp(1, 100),
+                delay_ms: u16::from_le_bytes([delay_lo, delay_hi]).clamp(50, 10_000),
+            }),
+            (MODE_ONE_SHOT, &[color_id, intensity]) => Ok(Self::OneShot {
+                color: color(color_id)?,
+                intensity: intensity.clamp(1, 100),
+            }),
+            (MODE_OFF..=MODE_ONE_SHOT, _) => Err(ParseError::Length),
+            _ => Err(ParseError::Value),
+        }
+    }
+
+    /// Encode back into the characteristic format, returns the number of bytes used
+    pub fn encode(&self, buf: &mut [u8; 5]) -> usize {
+        match *self {
+            Self::Off => {
+                buf[0] = MODE_OFF;
+                1
+            }
+            Self::Constant { red, green, blue } => {
+                *buf = [MODE_CONSTANT, red, green, blue, 0];
+                4
+            }
+            Self::Breathe {
+                color,
+                intensity,
+                delay_ms,
+            } => {
+                let [lo, hi] = delay_ms.to_le_bytes();
+                *buf = [MODE_BREATHE, color as u8, intensity, lo, hi];
+                5
+            }
+            Self::OneShot { color, intensity } => {
+                *buf = [MODE_ONE_SHOT, color as u8, intensity, 0, 0];
+                3
+            }
+        }
+    }
+
+    /// Matrix brightness (`0..=MAX_LEVEL`) at `elapsed_ms` since the mode was set.
+    ///
+    /// The matrix has no colour, so only the intensity is used. `None` means the
+    /// override has ended and the display should go back to showing readings.
+    pub fn level(&self, elapsed_ms: u32) -> Option<u8> {
+        match *self {
+            Self::Off => None,
+            Self::Constant { red, green, blue } => {
+                let max = red.max(green).max(blue) as u32;
+                Some(((max * MAX_LEVEL as u32 + 127) / 255) as u8)
+            }
+            Self::Breathe {
+                intensity,
+                delay_ms,
+                ..
+            } => {
+                let period = 2 * RAMP_MS + delay_ms as u32;
+                Some(ramp(intensity, elapsed_ms % period))
+            }
+            Self::OneShot { intensity, .. } => {
+                (elapsed_ms < 2 * RAMP_MS).then(|| ramp(intensity, elapsed_ms))
+            }
+        }
+    }
+}
+
+// 三角波：RAMP_MS 内渐亮，再用 RAMP_MS 渐暗，之后熄灭
+fn ramp(intensity: u8, t_ms: u32) -> u8 {
+    let peak = intensity as u32 * MAX_LEVEL as u32;
+    let scaled = match t_ms {
+        t if t < RAMP_MS => peak * t / RAMP_MS,
+        t if t < 2 * RAMP_MS => peak * (2 * RAMP_MS - t) / RAMP_MS,
+        _ => 0,
+    };
+    ((scaled + 50) / 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(led: Led) -> Led {
+        let mut buf = [0; 5];
+        let len = led.encode(&mut buf);
+        Led::parse(&buf[..len]).unwrap()
+    }
+
+    #[test]
+    fn parses_every_mode() {
+        assert_eq!(Led::parse(&[0]), Ok(Led::Off));
+        assert_eq!(
+            Led::parse(&[1, 10, 200, 30]),
+            Ok(Led::Constant {
+                red: 10,
+                green: 200,
+                blue: 30
+            })
+        );
+        assert_eq!(
+            Led::parse(&[2, 4, 50, 0xE8, 0x03]),
+            Ok(Led::Breathe {
+                color: LedColor::Blue,
+                intensity: 50,
+                delay_ms: 1000
+            })
+        );
+        assert_eq!(
+            Led::parse(&[3, 7, 80]),
+            Ok(Led::OneShot {
+                color: LedColor::White,
+                intensity: 80
+            })
+        );
+    }
+
+    #[test]
+    fn parameters_are_clamped() {
+        assert_eq!(
+            Led::parse(&[2, 1, 0, 0, 0]),
+            Ok(Led::Breathe {
+                color: LedColor::Red,
+                intensity: 1,
+                delay_ms: 50
+            })
+        );
+        assert_eq!(
+            Led::parse(&[3, 2, 255]),
+            Ok(Led::OneShot {
+                color: LedColor::Green,
+                intensity: 100
+            })
+        );
+        let Ok(Led::Breathe { delay_ms, .. }) = Led::parse(&[2, 1, 100, 0xFF, 0xFF]) else {
+            panic!();
+        };
+        assert_eq!(delay_ms, 10_000);
+    }
+
+    #[test]
+    fn rejects_wrong_lengths() {
+        for data in [
+            &[][..],
+            &[0, 0],
+            &[1, 1, 2],
+            &[1, 1, 2, 3, 4],
+            &[2, 1, 50, 0],
+            &[3, 1],
+        ] {
+            assert_eq!(Led::parse(data), Err(ParseError::Length), "{data:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_mode_and_colour() {
+        assert_eq!(Led::parse(&[4]), Err(ParseError::Value));
+        assert_eq!(Led::parse(&[0xFF, 1, 2]), Err(ParseError::Value));
+        assert_eq!(Led::parse(&[2, 0, 50, 0, 1]), Err(ParseError::Value));
+        assert_eq!(Led::parse(&[3, 8, 50]), Err(ParseError::Value));
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        for led in [
+            Led::Off,
+            Led::Constant {
+                red: 1,
+                green: 2,
+                blue: 3,
+            },
+            Led::Breathe {
+                color: LedColor::Cyan,
+                intensity: 20,
+                delay_ms: 3500,
+            },
+            Led::OneShot {
+                color: LedColor::Purple,
+                intensity: 100,
+            },
+        ] {
+            assert_eq!(round_trip(led), led);
+        }
+    }
+
+    #[test]
+    fn constant_level_follows_brightest_channel() {
+        let level = |red, green, blue| Led::Constant { red, green, blue }.level(0);
+        assert_eq!(level(0, 0, 0), Some(0));
+        assert_eq!(level(255, 0, 0), Some(MAX_LEVEL));
+        assert_eq!(level(0, 128, 20), Some(5));
+    }
+
+    #[test]
+    fn breathe_ramps_and_repeats() {
+        let led = Led::Breathe {
+            color: LedColor::Red,
+            intensity: 100,
+            delay_ms: 500,
+        };
+        assert_eq!(led.level(0), Some(0));
+        assert_eq!(led.level(500), Some(5));
+        assert_eq!(led.level(1000), Some(MAX_LEVEL));
+        assert_eq!(led.level(1500), Some(5));
+        // 渐暗后熄灭 delay_ms，再重复
+        assert_eq!(led.level(2200), Some(0));
+        assert_eq!(led.level(2500 + 1000), Some(MAX_LEVEL));
+    }
+
+    #[test]
+    fn one_shot_ends() {
+        let led = Led::OneShot {
+            color: LedColor::Red,
+            intensity: 50,
+        };
+        assert_eq!(led.level(1000), Some(5));
+        assert_eq!(led.level(1999), Some(0));
+        assert_eq!(led.level(2000), None);
+        assert_eq!(Led::Off.level(0), None);
+    }
+}
@@ -0,0 +1,189 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DisplayMode {
+    Co2,
+    Temperature,
+    Humidity,
+    /// 依次显示 CO2、温度和湿度
+    Carousel,
+    BarOnly,
+    Off,
+    /// 露点和舒适度，放在最后保持 BLE 中已有模式的编号
+    DewPoint,
+}
+
+impl DisplayMode {
+    /// Next mode for the A button, `Off` is only reachable through toggling
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Co2 => Self::Temperature,
+            Self::Temperature => Self::Humidity,
+            Self::Humidity => Self::DewPoint,
+            Self::DewPoint => Self::Carousel,
+            Self::Carousel => Self::BarOnly,
+            Self::BarOnly | Self::Off => Self::Co2,
+        }
+    }
+}
+
+impl TryFrom<u8> for DisplayMode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Co2,
+            1 => Self::Temperature,
+            2 => Self::Humidity,
+            3 => Self::Carousel,
+            4 => Self::BarOnly,
+            5 => Self::Off,
+            6 => Self::DewPoint,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl From<DisplayMode> for u8 {
+    fn from(value: DisplayMode) -> Self {
+        value as u8
+    }
+}
+
+/// Display mode selection shared by the buttons and BLE
+pub struct ModeManager {
+    mode: DisplayMode,
+    // 关闭前的模式，再次打开时恢复
+    resume: DisplayMode,
+}
+
+impl Default for ModeManager {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Co2,
+            resume: DisplayMode::Co2,
+        }
+    }
+}
+
+impl ModeManager {
+    pub const fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    pub fn set(&mut self, mode: DisplayMode) {
+        if mode == DisplayMode::Off && self.mode != DisplayMode::Off {
+            self.resume = self.mode;
+        }
+        self.mode = mode;
+    }
+
+    /// Cycle to the next mode, or wake the display without changing mode when off
+    pub fn next(&mut self) {
+        self.mode = match self.mode {
+            DisplayMode::Off => self.resume,
+            mode => mode.next(),
+        };
+    }
+
+    /// Leave `Off` and return to the last mode, other modes are unchanged
+    pub fn wake(&mut self) {
+        if self.mode == DisplayMode::Off {
+            self.mode = self.resume;
+        }
+    }
+
+    pub fn toggle_off(&mut self) {
+        match self.mode {
+            DisplayMode::Off => self.mode = self.resume,
+            _ => self.set(DisplayMode::Off),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [DisplayMode; 7] = [
+        DisplayMode::Co2,
+        DisplayMode::Temperature,
+        DisplayMode::Humidity,
+        DisplayMode::Carousel,
+        DisplayMode::BarOnly,
+        DisplayMode::Off,
+        DisplayMode::DewPoint,
+    ];
+
+    #[test]
+    fn button_cycle_skips_off() {
+        let mut mode = DisplayMode::Co2;
+        let mut seen = [DisplayMode::Off; 6];
+        for seen in &mut seen {
+            *seen = mode;
+            mode = mode.next();
+        }
+        assert_eq!(mode, DisplayMode::Co2);
+        assert_eq!(
+            seen,
+            [
+                DisplayMode::Co2,
+                DisplayMode::Temperature,
+                DisplayMode::Humidity,
+                DisplayMode::DewPoint,
+                DisplayMode::Carousel,
+                DisplayMode::BarOnly,
+            ]
+        );
+        assert_eq!(DisplayMode::Off.next(), DisplayMode::Co2);
+    }
+
+    #[test]
+    fn ble_values_round_trip() {
+        for (value, mode) in ALL.into_iter().enumerate() {
+            assert_eq!(u8::from(mode), value as u8);
+            assert_eq!(DisplayMode::try_from(value as u8), Ok(mode));
+        }
+        assert_eq!(DisplayMode::try_from(7), Err(()));
+        assert_eq!(DisplayMode::try_from(0xFF), Err(()));
+    }
+
+    #[test]
+    fn off_resumes_previous_mode() {
+        let mut manager = ModeManager::default();
+        assert_eq!(manager.mode(), DisplayMode::Co2);
+        manager.next();
+        manager.next();
+        assert_eq!(manager.mode(), DisplayMode::Humidity);
+
+        manager.toggle_off();
+        assert_eq!(manager.mode(), DisplayMode::Off);
+        // 关闭时按 A 只唤醒，不切换模式
+        manager.next();
+        assert_eq!(manager.mode(), DisplayMode::Humidity);
+
+        manager.toggle_off();
+        manager.toggle_off();
+        assert_eq!(manager.mode(), DisplayMode::Humidity);
+    }
+
+    #[test]
+    fn wake_only_leaves_off() {
+        let mut manager = ModeManager::default();
+        manager.set(DisplayMode::Carousel);
+        manager.wake();
+        assert_eq!(manager.mode(), DisplayMode::Carousel);
+        manager.set(DisplayMode::Off);
+        manager.wake();
+        assert_eq!(manager.mode(), DisplayMode::Carousel);
+    }
+
+    #[test]
+    fn setting_off_twice_keeps_resume_mode() {
+        let mut manager = ModeManager::default();
+        manager.set(DisplayMode::BarOnly);
+        manager.set(DisplayMode::Off);
+        manager.set(DisplayMode::Off);
+        manager.toggle_off();
+        assert_eq!(manager.mode(), DisplayMode::BarOnly);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::motion::orientation::Orientation;
+
+use super::Glyph;
+
+/// Clockwise rotation applied to frames before they are shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarter,
+}
+
+impl From<Orientation> for Rotation {
+    fn from(value: Orientation) -> Self {
+        match value {
+            Orientation::Portrait => Self::None,
+            Orientation::Landscape => Self::Quarter,
+            Orientation::ReversePortrait => Self::Half,
+            Orientation::ReverseLandscape => Self::ThreeQuarter,
+        }
+    }
+}
+
+fn is_set(pixels: &Glyph, x: usize, y: usize) -> bool {
+    pixels[y] & (0b10000 >> x) != 0
+}
+
+/// Rotate a 5x5 frame clockwise
+pub fn rotate(pixels: Glyph, rotation: Rotation) -> Glyph {
+    let mut out = [0u8; 5];
+    for (y, row) in out.iter_mut().enumerate() {
+        for x in 0..5 {
+            let (sx, sy) = match rotation {
+                Rotation::None => (x, y),
+                Rotation::Quarter => (y, 4 - x),
+                Rotation::Half => (4 - x, 4 - y),
+                Rotation::ThreeQuarter => (4 - y, x),
+            };
+            if is_set(&pixels, sx, sy) {
+                *row |= 0b10000 >> x;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP_LEFT: Glyph = [0b10000, 0, 0, 0, 0];
+    const UP: Glyph = [0b00100, 0b01110, 0b10101, 0b00100, 0b00100];
+    // 不对称的图案，任何旋转都不会与自身重合
+    const ASYMMETRIC: Glyph = [0b11000, 0b10000, 0b10110, 0b00010, 0b00011];
+
+    #[test]
+    fn rotation_for_each_orientation() {
+        let corners = [
+            (Orientation::Portrait, [0b10000, 0, 0, 0, 0]),
+            (Orientation::Landscape, [0b00001, 0, 0, 0, 0]),
+            (Orientation::ReversePortrait, [0, 0, 0, 0, 0b00001]),
+            (Orientation::ReverseLandscape, [0, 0, 0, 0, 0b10000]),
+        ];
+        for (orientation, expected) in corners {
+            assert_eq!(
+                rotate(TOP_LEFT, orientation.into()),
+                expected,
+                "{orientation:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn arrow_turns_clockwise() {
+        let right = [0b00100, 0b00010, 0b11111, 0b00010, 0b00100];
+        let down = [0b00100, 0b00100, 0b10101, 0b01110, 0b00100];
+        let left = [0b00100, 0b01000, 0b11111, 0b01000, 0b00100];
+        assert_eq!(rotate(UP, Rotation::None), UP);
+        assert_eq!(rotate(UP, Rotation::Quarter), right);
+        assert_eq!(rotate(UP, Rotation::Half), down);
+        assert_eq!(rotate(UP, Rotation::ThreeQuarter), left);
+    }
+
+    #[test]
+    fn rotations_compose() {
+        let quarter = |glyph| rotate(glyph, Rotation::Quarter);
+        assert_eq!(
+            quarter(quarter(ASYMMETRIC)),
+            rotate(ASYMMETRIC, Rotation::Half)
+        );
+        assert_eq!(
+            quarter(rotate(ASYMMETRIC, Rotation::Half)),
+            rotate(ASYMMETRIC, Rotation::ThreeQuarter)
+        );
+        assert_eq!(
+            quarter(rotate(ASYMMETRIC, Rotation::ThreeQuarter)),
+            ASYMMETRIC
+        );
+        for rotation in [Rotation::Quarter, Rotation::Half, Rotation::ThreeQuarter] {
+            let rotated = rotate(ASYMMETRIC, rotation);
+            assert_ne!(rotated, ASYMMETRIC, "{rotation:?}");
+            // 像素数量不变
+            let count = |glyph: Glyph| glyph.iter().map(|row| row.count_ones()).sum::<u32>();
+            assert_eq!(count(rotated), count(ASYMMETRIC));
+        }
+    }
+}
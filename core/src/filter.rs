@@ -0,0 +1,193 @@
+/// Limits of the validity filter for one sensor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FilterConfig {
+    /// Measurements discarded after the sensor starts
+    pub warm_up: u8,
+    pub min_ppm: u16,
+    pub max_ppm: u16,
+}
+
+impl Default for FilterConfig {
+    // SCD4x 的输出上限；室外约 420 ppm，下限留出基线偏差的余量
+    fn default() -> Self {
+        Self {
+            warm_up: 3,
+            min_ppm: 300,
+            max_ppm: 40_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Filtered {
+    /// Still within the warm-up period, the measurement was discarded
+    WarmingUp,
+    /// The sensor reported no concentration at all
+    Invalid,
+    /// Outside the plausible range of the sensor, the raw reading is rejected
+    OutOfRange(u16),
+    Valid(u16),
+}
+
+/// Validation of raw CO2 readings: warm-up, range check and a median of three
+/// that removes single-sample spikes
+pub struct ValidityFilter {
+    config: FilterConfig,
+    discarded: u8,
+    // 最近三个有效读数，len 为已填充的数量
+    window: [u16; 3],
+    len: usize,
+}
+
+impl ValidityFilter {
+    pub const fn new(config: FilterConfig) -> Self {
+        Self {
+            config,
+            discarded: 0,
+            window: [0; 3],
+            len: 0,
+        }
+    }
+
+    pub const fn warming_up(&self) -> bool {
+        self.discarded < self.config.warm_up
+    }
+
+    /// Start over, e.g. after the sensor was restarted
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    pub fn push(&mut self, co2_ppm: u16) -> Filtered {
+        if self.warming_up() {
+            self.discarded += 1;
+            return Filtered::WarmingUp;
+        }
+        // 0 ppm 是传感器的无效读数，不参与中值
+        if co2_ppm == 0 {
+            return Filtered::Invalid;
+        }
+        // 超出范围的读数不能代表实际浓度，也不参与中值
+        if !(self.config.min_ppm..=self.config.max_ppm).contains(&co2_ppm) {
+            return Filtered::OutOfRange(co2_ppm);
+        }
+
+        self.window.rotate_right(1);
+        self.window[0] = co2_ppm;
+        self.len = (self.len + 1).min(self.window.len());
+        Filtered::Valid(median(&self.window[..self.len]))
+    }
+}
+
+// 不足三个时取较早的读数，单个尖峰需要两次确认才会输出
+fn median(values: &[u16]) -> u16 {
+    match *values {
+        [a, b, c] => a.max(b).min(a.min(b).max(c)),
+        [_, b] => b,
+        [a] => a,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 跳过预热后的过滤器
+    fn warmed_up(config: FilterConfig) -> ValidityFilter {
+        let mut filter = ValidityFilter::new(config);
+        while filter.warming_up() {
+            filter.push(800);
+        }
+        filter
+    }
+
+    #[test]
+    fn discards_readings_while_warming_up() {
+        let mut filter = ValidityFilter::new(FilterConfig::default());
+        for _ in 0..3 {
+            assert!(filter.warming_up());
+            assert_eq!(filter.push(5000), Filtered::WarmingUp);
+        }
+        assert!(!filter.warming_up());
+        assert_eq!(filter.push(600), Filtered::Valid(600));
+    }
+
+    #[test]
+    fn no_warm_up() {
+        let mut filter = ValidityFilter::new(FilterConfig {
+            warm_up: 0,
+            ..FilterConfig::default()
+        });
+        assert_eq!(filter.push(600), Filtered::Valid(600));
+    }
+
+    #[test]
+    fn rejects_zero() {
+        let mut filter = warmed_up(FilterConfig::default());
+        assert_eq!(filter.push(0), Filtered::Invalid);
+        assert_eq!(filter.push(600), Filtered::Valid(600));
+    }
+
+    #[test]
+    fn rejects_readings_below_range() {
+        let mut filter = warmed_up(FilterConfig::default());
+        assert_eq!(filter.push(299), Filtered::OutOfRange(299));
+        // 被拒绝的读数不影响中值
+        assert_eq!(filter.push(500), Filtered::Valid(500));
+        assert_eq!(filter.push(510), Filtered::Valid(500));
+        assert_eq!(filter.push(520), Filtered::Valid(510));
+    }
+
+    #[test]
+    fn keeps_readings_just_below_400() {
+        let mut filter = warmed_up(FilterConfig::default());
+        assert_eq!(filter.push(390), Filtered::Valid(390));
+        assert_eq!(filter.push(300), Filtered::Valid(390));
+    }
+
+    #[test]
+    fn rejects_readings_above_range() {
+        let mut filter = warmed_up(FilterConfig {
+            max_ppm: 10_000,
+            ..FilterConfig::default()
+        });
+        assert_eq!(filter.push(10_001), Filtered::OutOfRange(10_001));
+        assert_eq!(filter.push(10_000), Filtered::Valid(10_000));
+    }
+
+    #[test]
+    fn median_removes_single_spike() {
+        let mut filter = warmed_up(FilterConfig::default());
+        assert_eq!(filter.push(800), Filtered::Valid(800));
+        assert_eq!(filter.push(810), Filtered::Valid(800));
+        assert_eq!(filter.push(9000), Filtered::Valid(810));
+        assert_eq!(filter.push(820), Filtered::Valid(820));
+        assert_eq!(filter.push(830), Filtered::Valid(830));
+    }
+
+    #[test]
+    fn median_follows_sustained_step() {
+        let mut filter = warmed_up(FilterConfig::default());
+        for _ in 0..3 {
+            filter.push(600);
+        }
+        assert_eq!(filter.push(1500), Filtered::Valid(600));
+        assert_eq!(filter.push(1500), Filtered::Valid(1500));
+    }
+
+    #[test]
+    fn reset_restarts_warm_up() {
+        let mut filter = warmed_up(FilterConfig::default());
+        filter.push(600);
+        filter.reset();
+        assert!(filter.warming_up());
+        for _ in 0..3 {
+            assert_eq!(filter.push(600), Filtered::WarmingUp);
+        }
+        // 中值窗口也被清空
+        assert_eq!(filter.push(700), Filtered::Valid(700));
+    }
+}
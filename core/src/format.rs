@@ -0,0 +1,56 @@
+use core::fmt::Write;
+
+use heapless::String;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Metric {
+    Co2,
+    Temperature,
+    Humidity,
+    DewPoint,
+}
+
+impl Metric {
+    // 露点加前缀 D，与温度区分
+    const fn prefix(self) -> &'static str {
+        match self {
+            Self::DewPoint => "D",
+            _ => "",
+        }
+    }
+
+    const fn unit(self) -> &'static str {
+        match self {
+            Self::Co2 => "",
+            Self::Temperature | Self::DewPoint => "C",
+            Self::Humidity => "%",
+        }
+    }
+}
+
+/// Scroll text for a reading, with a leading space so it scrolls in from the edge
+pub fn reading_text(metric: Metric, value: i32) -> String<8> {
+    let mut txt = String::new();
+    write!(&mut txt, " {}{}{}", metric.prefix(), value, metric.unit()).ok();
+    txt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_text_has_leading_space_and_unit() {
+        assert_eq!(reading_text(Metric::Co2, 1234).as_str(), " 1234");
+        assert_eq!(reading_text(Metric::Temperature, -5).as_str(), " -5C");
+        assert_eq!(reading_text(Metric::Humidity, 45).as_str(), " 45%");
+        assert_eq!(reading_text(Metric::DewPoint, 12).as_str(), " D12C");
+    }
+
+    #[test]
+    fn reading_text_fits_largest_values() {
+        assert_eq!(reading_text(Metric::Co2, 40_000).as_str(), " 40000");
+        assert_eq!(reading_text(Metric::DewPoint, -40).as_str(), " D-40C");
+    }
+}
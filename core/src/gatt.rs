@@ -0,0 +1,87 @@
+/// `#[repr(C, packed)]` structs of primitives, sent over GATT as their raw bytes
+pub trait PackedCStruct: Copy {}
+
+pub const fn as_bytes<T>(t: &T) -> &[u8] {
+    // SAFETY
+    // - Slice is of type u8 so data is guaranteed valid for reads of any length
+    // - Data and len are tied to the address and size of the type
+    unsafe { core::slice::from_raw_parts((t as *const T) as *const u8, core::mem::size_of::<T>()) }
+}
+
+/// Decode a packed struct, None when the length doesn't match
+pub fn from_bytes<T: PackedCStruct>(data: &[u8]) -> Option<T> {
+    if data.len() != core::mem::size_of::<T>() {
+        return None;
+    }
+    // SAFETY
+    // - Pointer is considered "valid" as per the rules outlined for validity in std::ptr v1.82.0
+    // - Pointer was generated from a slice of bytes matching the size of the type, and all packed C structures composed of primitives are valid for all possible configurations of bits
+    // - PackedCStruct trait is constrained to require Copy
+    unsafe { Some((data.as_ptr() as *const T).read_unaligned()) }
+}
+
+#[cfg(feature = "trouble")]
+#[macro_export]
+macro_rules! impl_fixedgattvalue {
+    ($type:ty) => {
+        impl $crate::gatt::PackedCStruct for $type {}
+
+        impl $crate::__trouble_host::types::gatt_traits::FixedGattValue for $type {
+            const SIZE: usize = core::mem::size_of::<Self>();
+
+            fn from_gatt(
+                data: &[u8],
+            ) -> Result<Self, $crate::__trouble_host::types::gatt_traits::FromGattError> {
+                $crate::gatt::from_bytes(data)
+                    .ok_or($crate::__trouble_host::types::gatt_traits::FromGattError::InvalidLength)
+            }
+
+            fn as_gatt(&self) -> &[u8] {
+                $crate::gatt::as_bytes(self)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, packed)]
+    #[derive(Clone, Copy)]
+    struct Packed {
+        flag: u8,
+        value: u16,
+        signed: i32,
+    }
+
+    impl PackedCStruct for Packed {}
+
+    #[test]
+    fn bytes_are_packed_little_endian() {
+        let packed = Packed {
+            flag: 0xAB,
+            value: 0x1234,
+            signed: -2,
+        };
+        assert_eq!(
+            as_bytes(&packed),
+            &[0xAB, 0x34, 0x12, 0xFE, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = [0x01, 0xE8, 0x03, 0x10, 0x00, 0x00, 0x80];
+        let packed: Packed = from_bytes(&data).unwrap();
+        let (flag, value, signed) = (packed.flag, packed.value, packed.signed);
+        assert_eq!((flag, value, signed), (1, 1000, i32::MIN + 0x10));
+        assert_eq!(as_bytes(&packed), &data);
+    }
+
+    #[test]
+    fn wrong_length_is_rejected() {
+        assert!(from_bytes::<Packed>(&[0; 6]).is_none());
+        assert!(from_bytes::<Packed>(&[0; 8]).is_none());
+    }
+}
@@ -0,0 +1,29 @@
+//! Hardware independent parts of the microbit_co2 firmware, kept separate so
+//! they build and test on the host.
+#![no_std]
+
+pub mod advertising;
+pub mod air_quality;
+pub mod alarm;
+pub mod button;
+pub mod climate;
+pub mod display;
+pub mod filter;
+pub mod format;
+pub mod gatt;
+pub mod microphone;
+pub mod motion;
+pub mod occupancy;
+pub mod prediction;
+pub mod rpa;
+pub mod sensor;
+#[cfg(test)]
+mod testing;
+pub mod trend;
+pub mod units;
+pub mod uuid;
+pub mod ventilation;
+
+#[cfg(feature = "trouble")]
+#[doc(hidden)]
+pub use trouble_host as __trouble_host;
@@ -0,0 +1,2 @@
+pub mod adpcm;
+pub mod level;
@@ -0,0 +1,195 @@
+const INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i16; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// Samples per Thingy microphone frame
+pub const FRAME_SAMPLES: usize = 256;
+/// 3 header bytes followed by two samples per byte
+pub const FRAME_SIZE: usize = 3 + FRAME_SAMPLES / 2;
+
+/// IMA/DVI ADPCM encoder producing frames in the Thingy TSS_MICROPHONE format.
+///
+/// Each frame starts with the predictor state (previous value as big endian
+/// `i16`, then the step index) so the decoder can resync on any frame. The
+/// first sample of a byte goes into the high nibble.
+pub struct AdpcmEncoder {
+    predicted: i16,
+    index: u8,
+    frame: [u8; FRAME_SIZE],
+    count: usize,
+}
+
+impl Default for AdpcmEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdpcmEncoder {
+    pub const fn new() -> Self {
+        Self {
+            predicted: 0,
+            index: 0,
+            frame: [0; FRAME_SIZE],
+            count: 0,
+        }
+    }
+
+    /// Encode one sample, returns the frame when it is complete
+    pub fn push(&mut self, sample: i16) -> Option<[u8; FRAME_SIZE]> {
+        if self.count == 0 {
+            let [high, low] = self.predicted.to_be_bytes();
+            self.frame[..3].copy_from_slice(&[high, low, self.index]);
+        }
+
+        let nibble = self.encode(sample);
+        let byte = &mut self.frame[3 + self.count / 2];
+        if self.count.is_multiple_of(2) {
+            *byte = nibble << 4;
+        } else {
+            *byte |= nibble;
+        }
+
+        self.count += 1;
+        if self.count == FRAME_SAMPLES {
+            self.count = 0;
+            Some(self.frame)
+        } else {
+            None
+        }
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let step = STEP_TABLE[self.index as usize] as i32;
+        let mut diff = sample as i32 - self.predicted as i32;
+        let mut nibble = 0u8;
+        if diff < 0 {
+            nibble = 8;
+            diff = -diff;
+        }
+
+        let mut delta = step >> 3;
+        if diff >= step {
+            nibble |= 4;
+            diff -= step;
+            delta += step;
+        }
+        if diff >= step >> 1 {
+            nibble |= 2;
+            diff -= step >> 1;
+            delta += step >> 1;
+        }
+        if diff >= step >> 2 {
+            nibble |= 1;
+            delta += step >> 2;
+        }
+
+        let predicted = if nibble & 8 != 0 {
+            self.predicted as i32 - delta
+        } else {
+            self.predicted as i32 + delta
+        };
+        self.predicted = predicted.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.index = (self.index as i32 + INDEX_TABLE[nibble as usize] as i32).clamp(0, 88) as u8;
+        nibble
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 参考输出由 CPython audioop.lin2adpcm（Intel/DVI IMA ADPCM）编码相同输入得到
+    const REFERENCE_FIRST: [u8; FRAME_SAMPLES / 2] = [
+        0xFF, 0xFF, 0xFF, 0xC4, 0x34, 0x33, 0x43, 0x34, 0xFF, 0xC0, 0x00, 0x10, 0x10, 0x11, 0x11,
+        0x12, 0xFF, 0x91, 0x00, 0x00, 0x01, 0x01, 0x10, 0x11, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0x01, 0x10, 0xFD, 0x00, 0x00, 0x00, 0x10, 0x01, 0x11, 0x11, 0xFF, 0x08, 0x00, 0x10, 0x00,
+        0x01, 0x01, 0x10, 0xFD, 0x00, 0x00, 0x00, 0x10, 0x10, 0x11, 0x02, 0xFF, 0x08, 0x01, 0x00,
+        0x00, 0x01, 0x01, 0x01, 0x77, 0x28, 0x08, 0x08, 0xFF, 0xA0, 0x80, 0x80, 0x73, 0x80, 0x80,
+        0x80, 0xFB, 0x80, 0x80, 0x80, 0x73, 0x80, 0x80, 0x80, 0xFB, 0x80, 0x80, 0x80, 0x73, 0x80,
+        0x08, 0x08, 0xFB, 0x08, 0x80, 0x80, 0x78, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x88, 0x00,
+        0x80, 0x88, 0x08, 0x00, 0x88, 0x00, 0x80, 0x88, 0x08, 0x00, 0x80, 0x88, 0x00, 0x88, 0x08,
+        0x00, 0x80, 0x88, 0x08, 0x00, 0x88, 0x00, 0x80,
+    ];
+    const REFERENCE_SECOND: [u8; FRAME_SAMPLES / 2] = [
+        0x77, 0x00, 0x00, 0x00, 0x10, 0x01, 0x01, 0x1F, 0xFF, 0x90, 0x08, 0x00, 0x08, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x11, 0x12, 0x12, 0x22, 0x33, 0x34, 0x33, 0x34, 0x34,
+        0x33, 0x43, 0x33, 0x52, 0x3F, 0xFF, 0xFA, 0x00, 0x08, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00,
+        0x10, 0x10, 0x11, 0x11, 0x12, 0x22, 0x23, 0x24, 0x23, 0x34, 0x33, 0x35, 0x23, 0x34, 0x33,
+        0x43, 0x3F, 0xFF, 0xFA, 0x80, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x12,
+        0x11, 0x21, 0x23, 0x23, 0x34, 0x33, 0x43, 0x33, 0x43, 0x43, 0x34, 0x33, 0x34, 0x3F, 0xFF,
+        0xFA, 0x80, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x10, 0x01, 0x10, 0x11, 0x21, 0x21, 0x23,
+        0x23, 0x34, 0x33, 0x43, 0x33, 0x43, 0x43, 0x34, 0x33, 0x34, 0x3F, 0xFF, 0xFA, 0x80, 0x00,
+        0x80, 0x00, 0x00, 0x00, 0x00, 0x10, 0x01, 0x10,
+    ];
+
+    // 锯齿波、满幅方波和静音，之后是小幅的伪随机信号
+    fn sample(i: i32) -> i16 {
+        (match i {
+            0..128 => (i * 1000) % 16_000 - 8000,
+            128..192 if (i / 8) % 2 == 0 => 30_000,
+            128..192 => -30_000,
+            192..256 => 0,
+            _ => (i * 37) % 2001 - 1000,
+        }) as i16
+    }
+
+    fn encode_frames() -> [[u8; FRAME_SIZE]; 2] {
+        let mut encoder = AdpcmEncoder::new();
+        let mut frames = [[0; FRAME_SIZE]; 2];
+        let mut count = 0;
+        for i in 0..2 * FRAME_SAMPLES as i32 {
+            if let Some(frame) = encoder.push(sample(i)) {
+                frames[count] = frame;
+                count += 1;
+            }
+        }
+        assert_eq!(count, 2);
+        frames
+    }
+
+    #[test]
+    fn matches_reference_encoder() {
+        let [first, second] = encode_frames();
+        assert_eq!(first[3..], REFERENCE_FIRST);
+        assert_eq!(second[3..], REFERENCE_SECOND);
+    }
+
+    #[test]
+    fn frame_header_carries_predictor_state() {
+        let [first, second] = encode_frames();
+        assert_eq!(first[..3], [0, 0, 0]);
+        // 参考编码器在第一帧之后的状态为 (11, 25)
+        assert_eq!(second[..3], [0x00, 0x0B, 25]);
+    }
+
+    #[test]
+    fn frame_only_when_complete() {
+        let mut encoder = AdpcmEncoder::default();
+        for _ in 1..FRAME_SAMPLES {
+            assert_eq!(encoder.push(0), None);
+        }
+        let frame = encoder.push(0).unwrap();
+        assert_eq!(frame.len(), 3 + 128);
+        // 静音时步长索引保持为 0，所有样本编码为 0
+        assert!(frame.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn saturates_at_full_scale() {
+        let mut encoder = AdpcmEncoder::new();
+        let mut last = None;
+        for _ in 0..2 * FRAME_SAMPLES {
+            last = encoder.push(i16::MAX).or(last);
+        }
+        let frame = last.unwrap();
+        assert_eq!(i16::from_be_bytes([frame[0], frame[1]]), i16::MAX);
+        assert!(frame[2] <= 88);
+    }
+}
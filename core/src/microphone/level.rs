@@ -0,0 +1,149 @@
+use core::f32::consts::PI;
+
+// 两级一阶高通，在 1kHz 以下大致模拟 A 计权的低频衰减
+const A_WEIGHTING_CORNER_HZ: f32 = 200.0;
+// 16 位满量程正弦波的有效值
+const FULL_SCALE_RMS: f32 = 23_170.0;
+/// dB SPL of a full-scale sine, derived from the -38 dBV/Pa mic sensitivity
+/// and the SAADC input range; only approximate without a reference meter
+pub const SPL_AT_FULL_SCALE_DB: f32 = 120.0;
+
+struct HighPass {
+    alpha: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl HighPass {
+    fn new(corner_hz: f32, sample_rate_hz: u32) -> Self {
+        Self {
+            alpha: 1.0 / (1.0 + 2.0 * PI * corner_hz / sample_rate_hz as f32),
+            last_input: 0.0,
+            last_output: 0.0,
+        }
+    }
+
+    fn filter(&mut self, input: f32) -> f32 {
+        self.last_output = self.alpha * (self.last_output + input - self.last_input);
+        self.last_input = input;
+        self.last_output
+    }
+}
+
+/// A-weighted-ish sound level over fixed windows
+pub struct SoundLevelMeter {
+    stages: [HighPass; 2],
+    sum_squares: f32,
+    count: u32,
+    window: u32,
+}
+
+impl SoundLevelMeter {
+    pub fn new(sample_rate_hz: u32, window_ms: u32) -> Self {
+        Self {
+            stages: [
+                HighPass::new(A_WEIGHTING_CORNER_HZ, sample_rate_hz),
+                HighPass::new(A_WEIGHTING_CORNER_HZ, sample_rate_hz),
+            ],
+            sum_squares: 0.0,
+            count: 0,
+            window: sample_rate_hz * window_ms / 1000,
+        }
+    }
+
+    /// Feed one 16-bit sample, returns the level in dB SPL at the end of each window
+    pub fn push(&mut self, sample: i16) -> Option<f32> {
+        let weighted = self
+            .stages
+            .iter_mut()
+            .fold(sample as f32, |x, stage| stage.filter(x));
+        self.sum_squares += weighted * weighted;
+        self.count += 1;
+        if self.count < self.window {
+            return None;
+        }
+
+        let level = spl_db(rms(self.sum_squares, self.count));
+        self.sum_squares = 0.0;
+        self.count = 0;
+        Some(level)
+    }
+}
+
+pub fn rms(sum_squares: f32, count: u32) -> f32 {
+    if count == 0 {
+        0.0
+    } else {
+        libm::sqrtf(sum_squares / count as f32)
+    }
+}
+
+/// Convert an RMS value of 16-bit samples to dB SPL, silence is clamped to 0 dB
+pub fn spl_db(rms: f32) -> f32 {
+    if rms <= 0.0 {
+        return 0.0;
+    }
+    (20.0 * libm::log10f(rms / FULL_SCALE_RMS) + SPL_AT_FULL_SCALE_DB).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE_HZ: u32 = 16_000;
+
+    fn sine_level(frequency_hz: f32, amplitude: f32) -> f32 {
+        let mut meter = SoundLevelMeter::new(SAMPLE_RATE_HZ, 1000);
+        let mut levels = (0..2 * SAMPLE_RATE_HZ).filter_map(|i| {
+            let t = i as f32 / SAMPLE_RATE_HZ as f32;
+            meter.push((amplitude * libm::sinf(2.0 * PI * frequency_hz * t)) as i16)
+        });
+        // 第一个窗口包含滤波器的建立过程
+        levels.next().unwrap();
+        levels.next().unwrap()
+    }
+
+    #[test]
+    fn full_scale_sine() {
+        let level = sine_level(1000.0, 32_767.0);
+        assert!((119.0..=120.5).contains(&level), "{level}");
+    }
+
+    #[test]
+    fn level_follows_amplitude() {
+        let loud = sine_level(1000.0, 32_767.0);
+        let quiet = sine_level(1000.0, 3276.7);
+        assert!(((loud - quiet) - 20.0).abs() < 0.1, "{loud} {quiet}");
+    }
+
+    #[test]
+    fn low_frequencies_are_attenuated() {
+        let mid = sine_level(1000.0, 10_000.0);
+        let low = sine_level(100.0, 10_000.0);
+        let very_low = sine_level(50.0, 10_000.0);
+        assert!(mid - low > 10.0, "{mid} {low}");
+        assert!(low - very_low > 5.0, "{low} {very_low}");
+    }
+
+    #[test]
+    fn one_level_per_window() {
+        let mut meter = SoundLevelMeter::new(SAMPLE_RATE_HZ, 250);
+        let levels = (0..SAMPLE_RATE_HZ)
+            .filter_map(|_| meter.push(0))
+            .collect::<heapless::Vec<f32, 8>>();
+        assert_eq!(levels, [0.0; 4]);
+    }
+
+    #[test]
+    fn spl_conversion() {
+        assert_eq!(spl_db(0.0), 0.0);
+        assert_eq!(spl_db(FULL_SCALE_RMS), SPL_AT_FULL_SCALE_DB);
+        assert!((spl_db(FULL_SCALE_RMS / 10.0) - 100.0).abs() < 0.01);
+        // 低于 0 dB 时截断
+        assert_eq!(spl_db(0.001), 0.0);
+        assert_eq!(rms(0.0, 0), 0.0);
+        assert_eq!(rms(4.0 * 10.0, 10), 2.0);
+        assert!((rms(2.0 * 10.0, 10) - core::f32::consts::SQRT_2).abs() < 1e-6);
+        assert!((spl_db(FULL_SCALE_RMS / 100.0) - 80.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod fusion;
+pub mod heading;
+pub mod orientation;
+pub mod pedometer;
+pub mod tap;
@@ -0,0 +1,262 @@
+/// Unit quaternion `[w, x, y, z]` rotating the board frame into the
+/// north-west-up world frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Quaternion(pub [f32; 4]);
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self([1.0, 0.0, 0.0, 0.0])
+    }
+}
+
+impl Quaternion {
+    /// From a rotation matrix whose rows are the world axes in board coordinates
+    pub fn from_rotation_matrix(m: [[f32; 3]; 3]) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = libm::sqrtf(trace + 1.0) * 2.0;
+            [
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            ]
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = libm::sqrtf(1.0 + m[0][0] - m[1][1] - m[2][2]) * 2.0;
+            [
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            ]
+        } else if m[1][1] > m[2][2] {
+            let s = libm::sqrtf(1.0 + m[1][1] - m[0][0] - m[2][2]) * 2.0;
+            [
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            ]
+        } else {
+            let s = libm::sqrtf(1.0 + m[2][2] - m[0][0] - m[1][1]) * 2.0;
+            [
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            ]
+        };
+        Self(q).normalized()
+    }
+
+    fn normalized(self) -> Self {
+        let norm = libm::sqrtf(self.0.iter().map(|v| v * v).sum::<f32>());
+        if norm == 0.0 {
+            Self::default()
+        } else {
+            Self(self.0.map(|v| v / norm))
+        }
+    }
+
+    pub fn rotation_matrix(&self) -> [[f32; 3]; 3] {
+        let [w, x, y, z] = self.0;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Roll, pitch and yaw in degrees
+    pub fn euler_deg(&self) -> [f32; 3] {
+        let m = self.rotation_matrix();
+        let roll = libm::atan2f(m[2][1], m[2][2]);
+        let pitch = libm::asinf((-m[2][0]).clamp(-1.0, 1.0));
+        let yaw = libm::atan2f(m[1][0], m[0][0]);
+        [roll, pitch, yaw].map(|v| v.to_degrees())
+    }
+}
+
+/// Orientation from accelerometer and magnetometer, smoothed towards each new
+/// measurement since there is no gyroscope to integrate
+#[derive(Default)]
+pub struct Fusion {
+    quaternion: Option<Quaternion>,
+}
+
+impl Fusion {
+    // 每个样本向测量值靠近的比例
+    const GAIN: f32 = 0.2;
+
+    pub const fn new() -> Self {
+        Self { quaternion: None }
+    }
+
+    /// Returns None until accel and mag give a usable (non-parallel) measurement
+    pub fn update(&mut self, accel: [f32; 3], mag: [f32; 3]) -> Option<Quaternion> {
+        let measured = Quaternion::from_rotation_matrix(measure(accel, mag)?);
+        let q = match self.quaternion {
+            None => measured,
+            Some(Quaternion(previous)) => {
+                // q 和 -q 表示同一旋转，插值前取同侧
+                let dot: f32 = previous.iter().zip(measured.0).map(|(a, b)| a * b).sum();
+                let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+                let blended: [f32; 4] = core::array::from_fn(|i| {
+                    previous[i] + (sign * measured.0[i] - previous[i]) * Self::GAIN
+                });
+                Quaternion(blended).normalized()
+            }
+        };
+        self.quaternion = Some(q);
+        Some(q)
+    }
+}
+
+// 由重力和磁场方向构造旋转矩阵，各行依次为北、西、上在板子坐标系中的方向
+fn measure(accel: [f32; 3], mag: [f32; 3]) -> Option<[[f32; 3]; 3]> {
+    let up = normalize(accel)?;
+    let west = normalize(cross(up, mag))?;
+    let north = cross(west, up);
+    Some([north, west, up])
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+    let norm = libm::sqrtf(v.iter().map(|x| x * x).sum::<f32>());
+    (norm > f32::EPSILON).then(|| v.map(|x| x / norm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 地磁场水平分量和垂直分量 (nT)，北半球磁倾角向下
+    const NORTH_NT: f32 = 20_000.0;
+    const DOWN_NT: f32 = 40_000.0;
+
+    fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() <= tolerance, "{actual:?} != {expected:?}");
+        }
+    }
+
+    // 给定世界坐标轴在板子坐标系中的方向，生成加速度计和磁力计读数
+    fn readings(north: [f32; 3], up: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+        let accel = up.map(|v| v * 1000.0);
+        let mag = core::array::from_fn(|i| north[i] * NORTH_NT - up[i] * DOWN_NT);
+        (accel, mag)
+    }
+
+    fn assert_orthonormal(m: [[f32; 3]; 3]) {
+        for i in 0..3 {
+            for j in 0..3 {
+                let dot: f32 = (0..3).map(|k| m[i][k] * m[j][k]).sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-5, "{m:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn flat_facing_north_is_identity() {
+        let (accel, mag) = readings([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        let q = Fusion::new().update(accel, mag).unwrap();
+        assert_close(&q.0, &[1.0, 0.0, 0.0, 0.0], 1e-6);
+        assert_close(&q.euler_deg(), &[0.0; 3], 1e-3);
+    }
+
+    #[test]
+    fn known_orientations() {
+        let (s, c) = (0.5, 0.866_025_4);
+        for (north, up, euler) in [
+            // 平放，x 轴指向西
+            ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, 90.0]),
+            // 平放，x 轴指向南
+            ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, 180.0]),
+            // 绕 x 轴横滚 30°
+            ([1.0, 0.0, 0.0], [0.0, s, c], [30.0, 0.0, 0.0]),
+            // 绕 y 轴俯仰 30°
+            ([c, 0.0, -s], [s, 0.0, c], [0.0, -30.0, 0.0]),
+        ] {
+            let (accel, mag) = readings(north, up);
+            let q = Fusion::new().update(accel, mag).unwrap();
+            let mut actual = q.euler_deg();
+            // 180° 与 -180° 相同
+            if (actual[2] + 180.0).abs() < 1e-3 {
+                actual[2] = 180.0;
+            }
+            assert_close(&actual, &euler, 0.01);
+            let m = q.rotation_matrix();
+            assert_close(&m[0], &north, 1e-5);
+            assert_close(&m[2], &up, 1e-5);
+        }
+    }
+
+    #[test]
+    fn arbitrary_readings_give_unit_quaternion_and_orthonormal_matrix() {
+        for (accel, mag) in [
+            ([120.0, -340.0, 910.0], [18_000.0, 9_000.0, -41_000.0]),
+            ([-700.0, 500.0, -300.0], [-5_000.0, 30_000.0, 12_000.0]),
+            ([0.0, -1000.0, 10.0], [25_000.0, -2_000.0, 33_000.0]),
+        ] {
+            let q = Fusion::new().update(accel, mag).unwrap();
+            let norm: f32 = q.0.iter().map(|v| v * v).sum();
+            assert!((norm - 1.0).abs() < 1e-6, "{q:?}");
+            let m = q.rotation_matrix();
+            assert_orthonormal(m);
+            // 与测量得到的矩阵一致
+            let measured = measure(accel, mag).unwrap();
+            for (row, expected) in m.iter().zip(measured) {
+                assert_close(row, &expected, 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_fields_are_rejected() {
+        assert_eq!(
+            Fusion::new().update([0.0, 0.0, 1000.0], [0.0, 0.0, -500.0]),
+            None
+        );
+        assert_eq!(Fusion::new().update([0.0; 3], [20_000.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn smoothing_converges_to_new_orientation() {
+        let mut fusion = Fusion::new();
+        let (accel, mag) = readings([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        fusion.update(accel, mag).unwrap();
+
+        let (accel, mag) = readings([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]);
+        let first = fusion.update(accel, mag).unwrap();
+        // 一步只靠近一部分
+        assert!(first.euler_deg()[2] > 5.0 && first.euler_deg()[2] < 85.0);
+        let mut q = first;
+        for _ in 0..60 {
+            q = fusion.update(accel, mag).unwrap();
+            let norm: f32 = q.0.iter().map(|v| v * v).sum();
+            assert!((norm - 1.0).abs() < 1e-6);
+        }
+        assert_close(&q.euler_deg(), &[0.0, 0.0, 90.0], 0.01);
+    }
+}
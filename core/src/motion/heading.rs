@@ -0,0 +1,202 @@
+/// Heading in degrees `[0, 360)` from the magnetic field, assumes the board lies flat
+pub fn heading_deg(mag: [f32; 3]) -> f32 {
+    let [x, y, _] = mag;
+    normalize(libm::atan2f(y, x).to_degrees())
+}
+
+/// Heading in degrees `[0, 360)` with the magnetic field projected onto the
+/// horizontal plane using the gravity direction from the accelerometer.
+/// Agrees with `heading_deg` when the board lies flat.
+pub fn tilt_compensated_deg(accel: [f32; 3], mag: [f32; 3]) -> f32 {
+    let [ax, ay, az] = accel;
+    let [mx, my, mz] = mag;
+    if ax == 0.0 && ay == 0.0 && az == 0.0 {
+        return heading_deg(mag);
+    }
+    let roll = libm::atan2f(ay, az);
+    let (sin_roll, cos_roll) = libm::sincosf(roll);
+    let pitch = libm::atan2f(-ax, ay * sin_roll + az * cos_roll);
+    let (sin_pitch, cos_pitch) = libm::sincosf(pitch);
+
+    let x = mx * cos_pitch + (my * sin_roll + mz * cos_roll) * sin_pitch;
+    let y = my * cos_roll - mz * sin_roll;
+    normalize(libm::atan2f(y, x).to_degrees())
+}
+
+fn normalize(degrees: f32) -> f32 {
+    if degrees < 0.0 {
+        degrees + 360.0
+    } else if degrees >= 360.0 {
+        degrees - 360.0
+    } else {
+        degrees
+    }
+}
+
+/// Thingy encodes the heading as Q16.16 degrees
+pub fn to_q16(degrees: f32) -> i32 {
+    (degrees * 65536.0) as i32
+}
+
+/// Hard-iron offset and diagonal soft-iron scale for the magnetometer (nT)
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    pub offset: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            offset: [0.0; 3],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl Calibration {
+    pub fn apply(&self, mag: [f32; 3]) -> [f32; 3] {
+        core::array::from_fn(|i| (mag[i] - self.offset[i]) * self.scale[i])
+    }
+}
+
+/// Collects the magnetometer extremes while the board is turned in all directions
+pub struct Calibrator {
+    min: [f32; 3],
+    max: [f32; 3],
+    samples: u32,
+}
+
+impl Default for Calibrator {
+    fn default() -> Self {
+        Self {
+            min: [f32::MAX; 3],
+            max: [f32::MIN; 3],
+            samples: 0,
+        }
+    }
+}
+
+impl Calibrator {
+    // 每个轴至少要覆盖的范围 (nT)，地磁场约 25-65 uT
+    const MIN_SPAN_NT: f32 = 20_000.0;
+    const MIN_SAMPLES: u32 = 50;
+
+    pub fn update(&mut self, mag: [f32; 3]) {
+        for (i, value) in mag.into_iter().enumerate() {
+            self.min[i] = self.min[i].min(value);
+            self.max[i] = self.max[i].max(value);
+        }
+        self.samples += 1;
+    }
+
+    /// The calibration, or None when the board wasn't turned far enough on every axis
+    pub fn finish(&self) -> Option<Calibration> {
+        if self.samples < Self::MIN_SAMPLES {
+            return None;
+        }
+        let span: [f32; 3] = core::array::from_fn(|i| self.max[i] - self.min[i]);
+        if span.iter().any(|&s| s < Self::MIN_SPAN_NT) {
+            return None;
+        }
+        let average = span.iter().sum::<f32>() / 3.0;
+        Some(Calibration {
+            offset: core::array::from_fn(|i| (self.max[i] + self.min[i]) / 2.0),
+            scale: core::array::from_fn(|i| average / span[i]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_deg(actual: f32, expected: f32) {
+        // 359.9 与 0 视为相同
+        let error = (actual - expected + 540.0) % 360.0 - 180.0;
+        assert!(error.abs() < 0.5, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn flat_cardinal_directions() {
+        assert_deg(heading_deg([20_000.0, 0.0, 40_000.0]), 0.0);
+        assert_deg(heading_deg([0.0, 20_000.0, 40_000.0]), 90.0);
+        assert_deg(heading_deg([-20_000.0, 0.0, 40_000.0]), 180.0);
+        assert_deg(heading_deg([0.0, -20_000.0, 40_000.0]), 270.0);
+        assert_deg(heading_deg([10_000.0, 10_000.0, 0.0]), 45.0);
+        assert_deg(heading_deg([10_000.0, -10_000.0, 0.0]), 315.0);
+    }
+
+    #[test]
+    fn tilt_compensation_agrees_when_flat() {
+        for mag in [[8660.0, 5000.0, 17321.0], [-9397.0, -3420.0, 17321.0]] {
+            let flat = tilt_compensated_deg([0.0, 0.0, 1000.0], mag);
+            assert_deg(flat, heading_deg(mag));
+        }
+        // 没有重力读数时退回到平放的计算
+        assert_deg(tilt_compensated_deg([0.0; 3], [0.0, 20_000.0, 0.0]), 90.0);
+    }
+
+    #[test]
+    fn tilted_known_vectors() {
+        // 磁倾角 60°，按横滚、俯仰和航向旋转后的板上读数 (mg, nT)
+        let vectors = [
+            ([0.0, 342.0, 940.0], [8660.0, 10622.0, 14566.0], 30.0),
+            ([423.0, 0.0, 906.0], [7320.0, 10000.0, 15698.0], 90.0),
+            ([-643.0, 383.0, 663.0], [-18332.0, 652.0, 7970.0], 200.0),
+            ([-259.0, -683.0, 683.0], [2347.0, -18124.0, 8124.0], 315.0),
+            ([-643.0, 383.0, 663.0], [-3473.0, 9848.0, 17057.0], 0.0),
+        ];
+        for (accel, mag, expected) in vectors {
+            assert_deg(tilt_compensated_deg(accel, mag), expected);
+            // 未补偿时倾斜会带来明显误差
+            let error = (heading_deg(mag) - expected + 540.0) % 360.0 - 180.0;
+            assert!(error.abs() > 5.0, "{expected}");
+        }
+    }
+
+    #[test]
+    fn q16_encoding() {
+        assert_eq!(to_q16(0.0), 0);
+        assert_eq!(to_q16(90.0), 90 << 16);
+        assert_eq!(to_q16(359.5), 359 << 16 | 0x8000);
+    }
+
+    #[test]
+    fn calibration_removes_offset_and_scale() {
+        let mut calibrator = Calibrator::default();
+        // 绕各轴转动，中心偏移 (5000, -3000, 1000)，y 轴灵敏度偏高
+        for i in 0..60 {
+            let angle = i as f32 * core::f32::consts::TAU / 60.0;
+            let (sin, cos) = angle.sin_cos();
+            calibrator.update([5000.0 + 30_000.0 * cos, -3000.0 + 36_000.0 * sin, 1000.0]);
+            calibrator.update([5000.0, -3000.0 + 36_000.0 * cos, 1000.0 + 30_000.0 * sin]);
+        }
+        let calibration = calibrator.finish().unwrap();
+        let [x, y, z] = calibration.offset;
+        assert!((x - 5000.0).abs() < 1.0 && (y + 3000.0).abs() < 1.0 && (z - 1000.0).abs() < 1.0);
+        let corrected = calibration.apply([5000.0 + 30_000.0, -3000.0 + 36_000.0, 1000.0]);
+        assert!((corrected[0] - corrected[1]).abs() < 1.0, "{corrected:?}");
+        assert_deg(
+            heading_deg(calibration.apply([5000.0, 33_000.0, 1000.0])),
+            90.0,
+        );
+    }
+
+    #[test]
+    fn calibration_needs_full_turn() {
+        let mut calibrator = Calibrator::default();
+        assert_eq!(calibrator.finish(), None);
+        // 只在水平面内转动，z 轴没有覆盖
+        for i in 0..100 {
+            let angle = i as f32 * core::f32::consts::TAU / 100.0;
+            calibrator.update([30_000.0 * angle.cos(), 30_000.0 * angle.sin(), 40_000.0]);
+        }
+        assert_eq!(calibrator.finish(), None);
+        assert_eq!(
+            Calibration::default().apply([1.0, 2.0, 3.0]),
+            [1.0, 2.0, 3.0]
+        );
+    }
+}
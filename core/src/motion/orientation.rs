@@ -0,0 +1,77 @@
+/// Thingy TMS_ORIENTATION values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Orientation {
+    Portrait = 0,
+    Landscape = 1,
+    ReversePortrait = 2,
+    ReverseLandscape = 3,
+}
+
+// 主轴分量低于该值时认为板子平放 (mg)
+const MIN_TILT_MG: i32 = 400;
+
+/// Orientation from the gravity direction in the board plane (mg).
+///
+/// None while the board lies flat or is held near a diagonal, callers keep
+/// the previous orientation so it doesn't flicker between two values.
+pub fn orientation(accel_mg: [i32; 3]) -> Option<Orientation> {
+    let [x, y, _] = accel_mg;
+    let (major, minor) = if x.abs() > y.abs() { (x, y) } else { (y, x) };
+    // 主轴需要明显大于另一轴，2:1 约为偏离对角线 18 度
+    if major.abs() < MIN_TILT_MG || major.abs() < minor.abs() * 2 {
+        return None;
+    }
+    Some(if x.abs() > y.abs() {
+        if x > 0 {
+            Orientation::Landscape
+        } else {
+            Orientation::ReverseLandscape
+        }
+    } else if y > 0 {
+        Orientation::ReversePortrait
+    } else {
+        Orientation::Portrait
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_edge_down() {
+        assert_eq!(orientation([0, -1000, 0]), Some(Orientation::Portrait));
+        assert_eq!(orientation([1000, 0, 0]), Some(Orientation::Landscape));
+        assert_eq!(
+            orientation([0, 1000, 0]),
+            Some(Orientation::ReversePortrait)
+        );
+        assert_eq!(
+            orientation([-1000, 0, 0]),
+            Some(Orientation::ReverseLandscape)
+        );
+        // 稍微倾斜仍然判断为同一方向
+        assert_eq!(orientation([300, -800, 500]), Some(Orientation::Portrait));
+    }
+
+    #[test]
+    fn flat_or_diagonal_is_undecided() {
+        assert_eq!(orientation([0, 0, 1000]), None);
+        assert_eq!(orientation([390, 0, 920]), None);
+        assert_eq!(orientation([700, -700, 0]), None);
+        assert_eq!(orientation([-700, 400, 0]), None);
+        assert_eq!(
+            orientation([-800, 400, 0]),
+            Some(Orientation::ReverseLandscape)
+        );
+    }
+
+    #[test]
+    fn thingy_values() {
+        assert_eq!(Orientation::Portrait as u8, 0);
+        assert_eq!(Orientation::Landscape as u8, 1);
+        assert_eq!(Orientation::ReversePortrait as u8, 2);
+        assert_eq!(Orientation::ReverseLandscape as u8, 3);
+    }
+}
@@ -0,0 +1,281 @@
+/// Lowest sample rate at which step peaks are still resolved
+pub const SAMPLE_HZ: u16 = 25;
+
+/// Sample rate for publishing at `frequency_hz`: the lowest multiple of it
+/// that is at least `SAMPLE_HZ`, so every n-th sample lands exactly on the
+/// requested rate.
+pub const fn sample_hz(frequency_hz: u16) -> u16 {
+    let frequency_hz = if frequency_hz == 0 { 1 } else { frequency_hz };
+    frequency_hz * SAMPLE_HZ.div_ceil(frequency_hz)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StepConfig {
+    /// Rise above the running mean that counts as a step peak (mg)
+    pub threshold_mg: u16,
+    /// The detector re-arms once the magnitude falls this far below the threshold (mg)
+    pub hysteresis_mg: u16,
+    /// Peaks closer together than this are one step
+    pub min_interval_ms: u16,
+    /// Steps needed within `carry_window_ms` to count as being carried
+    pub carry_steps: u8,
+    pub carry_window_ms: u16,
+}
+
+impl Default for StepConfig {
+    fn default() -> Self {
+        Self {
+            threshold_mg: 150,
+            hysteresis_mg: 100,
+            min_interval_ms: 250,
+            carry_steps: 4,
+            carry_window_ms: 6000,
+        }
+    }
+}
+
+impl StepConfig {
+    // 最近步伐时间的容量
+    const MAX_CARRY_STEPS: u8 = 8;
+
+    pub const fn is_valid(&self) -> bool {
+        self.threshold_mg > 0
+            && self.hysteresis_mg <= self.threshold_mg
+            && self.min_interval_ms > 0
+            && self.carry_steps > 0
+            && self.carry_steps <= Self::MAX_CARRY_STEPS
+            && self.carry_window_ms > 0
+    }
+}
+
+/// Step counter using peak detection on the acceleration magnitude
+pub struct Pedometer {
+    config: StepConfig,
+    // 加速度模长的滑动均值 (mg)，首个样本之前为 None
+    mean: Option<f32>,
+    armed: bool,
+    steps: u32,
+    last_step_ms: Option<u32>,
+    // 最近 carry_steps 步的时间，环形存放
+    recent: [u32; StepConfig::MAX_CARRY_STEPS as usize],
+    recent_len: usize,
+}
+
+impl Pedometer {
+    // 均值低通滤波系数，约 1 秒的时间常数
+    const MEAN_ALPHA: f32 = 0.04;
+
+    pub fn new(config: StepConfig) -> Self {
+        Self {
+            config,
+            mean: None,
+            armed: true,
+            steps: 0,
+            last_step_ms: None,
+            recent: [0; StepConfig::MAX_CARRY_STEPS as usize],
+            recent_len: 0,
+        }
+    }
+
+    /// Apply new limits, the count is kept and peak detection starts over
+    pub fn configure(&mut self, config: StepConfig) {
+        *self = Self {
+            steps: self.steps,
+            ..Self::new(config)
+        };
+    }
+
+    pub const fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Feed one accelerometer sample, returns true when it completes a step
+    pub fn update(&mut self, accel_mg: [i32; 3], now_ms: u32) -> bool {
+        let magnitude = libm::sqrtf(accel_mg.iter().map(|&v| (v as f32) * (v as f32)).sum());
+        let mean = match self.mean {
+            Some(mean) => mean + (magnitude - mean) * Self::MEAN_ALPHA,
+            None => magnitude,
+        };
+        self.mean = Some(mean);
+
+        let rise = magnitude - mean;
+        let threshold = self.config.threshold_mg as f32;
+        if !self.armed {
+            if rise < threshold - self.config.hysteresis_mg as f32 {
+                self.armed = true;
+            }
+            return false;
+        }
+        if rise < threshold {
+            return false;
+        }
+
+        self.armed = false;
+        let too_soon = self
+            .last_step_ms
+            .is_some_and(|last| now_ms.wrapping_sub(last) < self.config.min_interval_ms as u32);
+        if too_soon {
+            return false;
+        }
+        self.last_step_ms = Some(now_ms);
+        self.steps = self.steps.wrapping_add(1);
+
+        let capacity = (self.config.carry_steps as usize).clamp(1, self.recent.len());
+        if self.recent_len < capacity {
+            self.recent_len += 1;
+        }
+        self.recent.copy_within(0..capacity - 1, 1);
+        self.recent[0] = now_ms;
+        true
+    }
+
+    /// Whether enough recent steps were seen for the unit to be on the move
+    pub fn carried(&self, now_ms: u32) -> bool {
+        let needed = (self.config.carry_steps as usize).clamp(1, self.recent.len());
+        self.recent_len >= needed
+            && now_ms.wrapping_sub(self.recent[needed - 1]) <= self.config.carry_window_ms as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const WALKING: &str = include_str!("../../../fixtures/walking.csv");
+
+    // 夹具中各段的结束时间 (ms)
+    const STILL_END: u32 = 10_000;
+    const FIRST_WALK_END: u32 = 40_000;
+    const TURNING_END: u32 = 50_000;
+    const SECOND_WALK_END: u32 = 70_000;
+
+    #[test]
+    fn sample_rate_is_a_multiple_of_the_requested_rate() {
+        for frequency_hz in 1..=200 {
+            let sample_hz = sample_hz(frequency_hz);
+            assert!(sample_hz >= SAMPLE_HZ, "{frequency_hz}");
+            assert!(sample_hz.is_multiple_of(frequency_hz), "{frequency_hz}");
+            // 不超过所需的最低采样率太多
+            assert!(sample_hz < SAMPLE_HZ + frequency_hz, "{frequency_hz}");
+        }
+        assert_eq!(sample_hz(10), 30);
+        assert_eq!(sample_hz(25), 25);
+        assert_eq!(sample_hz(100), 100);
+    }
+
+    fn replay(pedometer: &mut Pedometer, from_ms: u32, to_ms: u32) -> u32 {
+        let before = pedometer.steps();
+        for [t_ms, x, y, z] in testing::rows::<4>(WALKING) {
+            let t_ms = t_ms as u32;
+            if (from_ms..to_ms).contains(&t_ms) {
+                pedometer.update([x, y, z], t_ms);
+            }
+        }
+        pedometer.steps() - before
+    }
+
+    #[test]
+    fn counts_fixture_steps() {
+        let mut pedometer = Pedometer::new(StepConfig::default());
+        assert_eq!(replay(&mut pedometer, 0, STILL_END), 0);
+        let first = replay(&mut pedometer, STILL_END, FIRST_WALK_END);
+        assert!((52..=55).contains(&first), "{first}");
+        assert!(pedometer.carried(FIRST_WALK_END));
+        // 缓慢翻转只改变方向不改变模长
+        assert_eq!(replay(&mut pedometer, FIRST_WALK_END, TURNING_END), 0);
+        assert!(!pedometer.carried(TURNING_END));
+        let second = replay(&mut pedometer, TURNING_END, SECOND_WALK_END);
+        assert!((42..=45).contains(&second), "{second}");
+        assert_eq!(replay(&mut pedometer, SECOND_WALK_END, u32::MAX), 0);
+        assert!(!pedometer.carried(SECOND_WALK_END + 10_000));
+    }
+
+    #[test]
+    fn threshold_too_high_for_fixture() {
+        let mut pedometer = Pedometer::new(StepConfig {
+            threshold_mg: 600,
+            hysteresis_mg: 100,
+            ..StepConfig::default()
+        });
+        assert_eq!(replay(&mut pedometer, 0, u32::MAX), 0);
+    }
+
+    #[test]
+    fn min_interval_merges_peaks() {
+        // 400 ms 一个峰值，最小间隔 500 ms 时只有每隔一个计数
+        let mut pedometer = Pedometer::new(StepConfig {
+            min_interval_ms: 500,
+            ..StepConfig::default()
+        });
+        pedometer.update([0, 0, 1000], 0);
+        for step in 1..=10u32 {
+            let t = step * 400;
+            pedometer.update([0, 0, 1400], t);
+            pedometer.update([0, 0, 1000], t + 200);
+        }
+        assert_eq!(pedometer.steps(), 5);
+    }
+
+    #[test]
+    fn rearms_only_below_hysteresis() {
+        let mut pedometer = Pedometer::new(StepConfig::default());
+        pedometer.update([0, 0, 1000], 0);
+        assert!(pedometer.update([0, 0, 1300], 1000));
+        // 仍高于阈值减回差，不会重新触发
+        assert!(!pedometer.update([0, 0, 1080], 2000));
+        assert!(!pedometer.update([0, 0, 1300], 3000));
+        assert!(!pedometer.update([0, 0, 1000], 4000));
+        assert!(pedometer.update([0, 0, 1300], 5000));
+        assert_eq!(pedometer.steps(), 2);
+    }
+
+    #[test]
+    fn configure_keeps_count() {
+        let mut pedometer = Pedometer::new(StepConfig::default());
+        replay(&mut pedometer, 0, FIRST_WALK_END);
+        let steps = pedometer.steps();
+        pedometer.configure(StepConfig {
+            threshold_mg: 200,
+            ..StepConfig::default()
+        });
+        assert_eq!(pedometer.steps(), steps);
+        assert!(!pedometer.carried(FIRST_WALK_END));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(StepConfig::default().is_valid());
+        let invalid = [
+            StepConfig {
+                threshold_mg: 0,
+                hysteresis_mg: 0,
+                ..StepConfig::default()
+            },
+            StepConfig {
+                hysteresis_mg: 200,
+                ..StepConfig::default()
+            },
+            StepConfig {
+                min_interval_ms: 0,
+                ..StepConfig::default()
+            },
+            StepConfig {
+                carry_steps: 0,
+                ..StepConfig::default()
+            },
+            StepConfig {
+                carry_steps: 9,
+                ..StepConfig::default()
+            },
+            StepConfig {
+                carry_window_ms: 0,
+                ..StepConfig::default()
+            },
+        ];
+        for config in invalid {
+            assert!(!config.is_valid(), "{config:?}");
+        }
+    }
+}
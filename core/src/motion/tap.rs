@@ -0,0 +1,138 @@
+/// Thingy tap directions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TapDirection {
+    XUp = 1,
+    XDown = 2,
+    YUp = 3,
+    YDown = 4,
+    ZUp = 5,
+    ZDown = 6,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TapEvent {
+    Tap { direction: TapDirection, count: u8 },
+    Shake,
+}
+
+impl TapEvent {
+    // 摇晃不在 Thingy 协议中，用次数 0 表示
+    const SHAKE_COUNT: u8 = 0;
+
+    /// TMS_TAP value: direction in the low byte, tap count in the high byte
+    pub fn to_gatt(self) -> u16 {
+        match self {
+            Self::Tap { direction, count } => u16::from_le_bytes([direction as u8, count]),
+            Self::Shake => u16::from_le_bytes([0, Self::SHAKE_COUNT]),
+        }
+    }
+}
+
+/// Decode CLICK_SRC_A
+pub fn decode_click(click_src: u8) -> Option<TapEvent> {
+    const IA: u8 = 1 << 6;
+    const DOUBLE: u8 = 1 << 5;
+    const SINGLE: u8 = 1 << 4;
+    const NEGATIVE: u8 = 1 << 3;
+
+    if click_src & IA == 0 {
+        return None;
+    }
+    let count = if click_src & DOUBLE != 0 {
+        2
+    } else if click_src & SINGLE != 0 {
+        1
+    } else {
+        return None;
+    };
+    let negative = click_src & NEGATIVE != 0;
+    let direction = match (click_src & 0b111, negative) {
+        (0b100, false) => TapDirection::ZUp,
+        (0b100, true) => TapDirection::ZDown,
+        (0b010, false) => TapDirection::YUp,
+        (0b010, true) => TapDirection::YDown,
+        (_, false) => TapDirection::XUp,
+        (_, true) => TapDirection::XDown,
+    };
+    Some(TapEvent::Tap { direction, count })
+}
+
+/// Decode INT1_SRC_A, any high event is a shake
+pub fn decode_shake(int1_src: u8) -> Option<TapEvent> {
+    const IA: u8 = 1 << 6;
+    const HIGH: u8 = 0b0010_1010;
+
+    (int1_src & IA != 0 && int1_src & HIGH != 0).then_some(TapEvent::Shake)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IA: u8 = 1 << 6;
+    const DOUBLE: u8 = 1 << 5;
+    const SINGLE: u8 = 1 << 4;
+    const NEGATIVE: u8 = 1 << 3;
+    const Z: u8 = 0b100;
+    const Y: u8 = 0b010;
+    const X: u8 = 0b001;
+
+    const fn tap(direction: TapDirection, count: u8) -> Option<TapEvent> {
+        Some(TapEvent::Tap { direction, count })
+    }
+
+    #[test]
+    fn click_directions() {
+        for (src, direction) in [
+            (Z, TapDirection::ZUp),
+            (Z | NEGATIVE, TapDirection::ZDown),
+            (Y, TapDirection::YUp),
+            (Y | NEGATIVE, TapDirection::YDown),
+            (X, TapDirection::XUp),
+            (X | NEGATIVE, TapDirection::XDown),
+        ] {
+            assert_eq!(
+                decode_click(IA | SINGLE | src),
+                tap(direction, 1),
+                "{src:#010b}"
+            );
+        }
+    }
+
+    #[test]
+    fn double_click_wins_over_single() {
+        assert_eq!(decode_click(IA | DOUBLE | Z), tap(TapDirection::ZUp, 2));
+        assert_eq!(
+            decode_click(IA | DOUBLE | SINGLE | Y | NEGATIVE),
+            tap(TapDirection::YDown, 2)
+        );
+    }
+
+    #[test]
+    fn click_without_interrupt_or_kind_is_ignored() {
+        assert_eq!(decode_click(0), None);
+        assert_eq!(decode_click(SINGLE | Z), None);
+        assert_eq!(decode_click(IA | Z), None);
+    }
+
+    #[test]
+    fn shake_needs_a_high_event() {
+        // 高于阈值的事件位为 XH、YH、ZH
+        assert_eq!(decode_shake(IA | 0b0000_0010), Some(TapEvent::Shake));
+        assert_eq!(decode_shake(IA | 0b0010_0000), Some(TapEvent::Shake));
+        assert_eq!(decode_shake(IA | 0b0001_0101), None);
+        assert_eq!(decode_shake(0b0010_1010), None);
+    }
+
+    #[test]
+    fn gatt_encoding() {
+        let event = TapEvent::Tap {
+            direction: TapDirection::YDown,
+            count: 2,
+        };
+        assert_eq!(event.to_gatt().to_le_bytes(), [4, 2]);
+        assert_eq!(TapEvent::Shake.to_gatt(), 0);
+    }
+}
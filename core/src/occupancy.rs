@@ -0,0 +1,208 @@
+/// Room parameters for the CO2 mass balance
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OccupancyConfig {
+    pub volume_m3: f32,
+    /// Outdoor air supplied to the room
+    pub ventilation_m3_per_h: f32,
+    /// CO2 exhaled per person
+    pub generation_l_per_h: f32,
+    pub outdoor_ppm: u16,
+}
+
+impl Default for OccupancyConfig {
+    // 约 20 平方米、2.5 米高的房间，每小时换气一次，久坐的成年人
+    fn default() -> Self {
+        Self {
+            volume_m3: 50.0,
+            ventilation_m3_per_h: 50.0,
+            generation_l_per_h: 18.0,
+            outdoor_ppm: 420,
+        }
+    }
+}
+
+impl OccupancyConfig {
+    /// Volume, ventilation and generation must be positive and finite
+    pub fn is_valid(&self) -> bool {
+        [
+            self.volume_m3,
+            self.ventilation_m3_per_h,
+            self.generation_l_per_h,
+        ]
+        .iter()
+        .all(|value| value.is_finite() && *value > 0.0)
+    }
+}
+
+/// Approximate number of people in the room.
+///
+/// Solves the mass balance `V dC/dt = N G - Q (C - C_out)` for `N`, with the
+/// concentration and its rate of change taken from the rolling averages.
+pub fn estimate(config: &OccupancyConfig, co2_ppm: u16, rate_ppm_per_min: f32) -> u8 {
+    let excess_ppm = co2_ppm.saturating_sub(config.outdoor_ppm) as f32;
+    // 每小时增加和被新风带走的 CO2 (升)，1 ppm 即每立方米 1 毫升
+    let accumulated = config.volume_m3 * rate_ppm_per_min * 60.0 / 1000.0;
+    let removed = config.ventilation_m3_per_h * excess_ppm / 1000.0;
+    let people = (accumulated + removed) / config.generation_l_per_h;
+    // 负值 as 转换为 0，超出范围饱和
+    (people + 0.5) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trend::TrendTracker;
+
+    // 按质量守恒以 5 秒步长模拟房间，每 5 秒读数一次，
+    // 返回每分钟结束时由滚动平均得到的估计人数
+    fn step_response(
+        config: &OccupancyConfig,
+        start_ppm: f32,
+        people: impl Fn(u32) -> u32,
+        minutes: u32,
+    ) -> impl Iterator<Item = (u32, u8)> {
+        let mut tracker = TrendTracker::new();
+        let mut co2 = start_ppm;
+        (0..minutes).map(move |minute| {
+            let mut averages = tracker.averages();
+            for step in 0..12 {
+                let generated = people(minute) as f32 * config.generation_l_per_h * 1000.0;
+                let removed = config.ventilation_m3_per_h * (co2 - config.outdoor_ppm as f32);
+                co2 += (generated - removed) / config.volume_m3 * 5.0 / 3600.0;
+                averages = tracker.push(minute * 60 + step * 5, co2 as u16);
+            }
+            let people = estimate(config, averages.avg_1min, averages.rate_ppm_per_min as f32);
+            (minute, people)
+        })
+    }
+
+    #[test]
+    fn steady_state() {
+        let config = OccupancyConfig::default();
+        // 两人时稳态超出室外 2 * 18 L/h / 50 m³/h = 720 ppm
+        assert_eq!(estimate(&config, 1140, 0.0), 2);
+        assert_eq!(estimate(&config, 420, 0.0), 0);
+        assert_eq!(estimate(&config, 300, 0.0), 0);
+    }
+
+    #[test]
+    fn rise_of_empty_room() {
+        let config = OccupancyConfig::default();
+        // 三人刚进入时每分钟上升 3 * 18 L/h / 50 m³ / 60 = 18 ppm
+        assert_eq!(estimate(&config, 420, 18.0), 3);
+    }
+
+    #[test]
+    fn falling_concentration_is_never_negative() {
+        let config = OccupancyConfig::default();
+        assert_eq!(estimate(&config, 900, -30.0), 0);
+        assert_eq!(estimate(&config, 420, -100.0), 0);
+    }
+
+    #[test]
+    fn saturates() {
+        let config = OccupancyConfig {
+            volume_m3: 2000.0,
+            ..OccupancyConfig::default()
+        };
+        assert_eq!(estimate(&config, 40_000, 1000.0), u8::MAX);
+    }
+
+    #[test]
+    fn constant_occupancy() {
+        let config = OccupancyConfig::default();
+        for people in [1, 4, 10] {
+            let estimates = step_response(&config, 420.0, |_| people, 120);
+            // 五分钟变化率需要先填满历史
+            for (minute, estimate) in estimates.skip(6) {
+                assert!(
+                    estimate.abs_diff(people as u8) <= 1,
+                    "{people} people, minute {minute}: {estimate}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn constant_occupancy_in_other_room() {
+        let config = OccupancyConfig {
+            volume_m3: 150.0,
+            ventilation_m3_per_h: 300.0,
+            generation_l_per_h: 20.0,
+            outdoor_ppm: 400,
+        };
+        let estimates = step_response(&config, 400.0, |_| 25, 90);
+        for (minute, estimate) in estimates.skip(6) {
+            assert!(estimate.abs_diff(25) <= 2, "minute {minute}: {estimate}");
+        }
+    }
+
+    #[test]
+    fn people_leaving() {
+        let config = OccupancyConfig::default();
+        // 四人待一小时后离开
+        let estimates: [(u32, u8); 120] = {
+            let mut all = step_response(
+                &config,
+                420.0,
+                |minute| if minute < 60 { 4 } else { 0 },
+                120,
+            );
+            core::array::from_fn(|_| all.next().unwrap())
+        };
+        assert!(estimates[50..60].iter().all(|&(_, n)| n.abs_diff(4) <= 1));
+        // 五分钟变化率追上之后估计为零
+        for &(minute, estimate) in &estimates[66..] {
+            assert!(estimate <= 1, "minute {minute}: {estimate}");
+        }
+        assert_eq!(estimates[119].1, 0);
+    }
+
+    #[test]
+    fn some_people_leaving() {
+        let config = OccupancyConfig::default();
+        let estimates = step_response(
+            &config,
+            420.0,
+            |minute| if minute < 60 { 6 } else { 2 },
+            120,
+        );
+        for (minute, estimate) in estimates.skip(70) {
+            assert!(estimate.abs_diff(2) <= 1, "minute {minute}: {estimate}");
+        }
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(OccupancyConfig::default().is_valid());
+        for invalid in [
+            OccupancyConfig {
+                volume_m3: 0.0,
+                ..OccupancyConfig::default()
+            },
+            OccupancyConfig {
+                ventilation_m3_per_h: 0.0,
+                ..OccupancyConfig::default()
+            },
+            OccupancyConfig {
+                ventilation_m3_per_h: -10.0,
+                ..OccupancyConfig::default()
+            },
+            OccupancyConfig {
+                generation_l_per_h: 0.0,
+                ..OccupancyConfig::default()
+            },
+            OccupancyConfig {
+                generation_l_per_h: f32::NAN,
+                ..OccupancyConfig::default()
+            },
+            OccupancyConfig {
+                volume_m3: f32::INFINITY,
+                ..OccupancyConfig::default()
+            },
+        ] {
+            assert!(!invalid.is_valid(), "{invalid:?}");
+        }
+    }
+}
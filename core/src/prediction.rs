@@ -0,0 +1,168 @@
+/// Minutes of history fitted, the mass-balance rise is close to linear over this span
+const FIT_MINUTES: usize = 10;
+/// Fewer points than this don't give a usable slope
+const MIN_POINTS: usize = 3;
+/// Slower rises (ppm/min) count as not rising
+const MIN_RISE: f32 = 1.0;
+/// Predictions further out are too uncertain to report
+pub const MAX_MINUTES: u16 = 8 * 60;
+
+/// Minutes until `threshold_ppm` is reached, 0 when already there and None
+/// when the concentration isn't rising towards it.
+///
+/// `current` is the concentration now, `history` the averages of the preceding
+/// minutes, most recent first. A least-squares line through them gives the rate.
+pub fn minutes_until(current: u16, history: &[u16], threshold_ppm: u16) -> Option<u16> {
+    if current >= threshold_ppm {
+        return Some(0);
+    }
+    let slope = slope_ppm_per_min(current, history)?;
+    if slope < MIN_RISE {
+        return None;
+    }
+    let minutes = (threshold_ppm - current) as f32 / slope;
+    // 向上取整，宁可早一点提醒
+    let whole = minutes as u16;
+    let minutes = whole + (minutes > whole as f32) as u16;
+    (minutes <= MAX_MINUTES).then_some(minutes)
+}
+
+// 最小二乘拟合的斜率，时间以分钟计，当前为 0，历史依次为 -1、-2 …
+fn slope_ppm_per_min(current: u16, history: &[u16]) -> Option<f32> {
+    let history = &history[..history.len().min(FIT_MINUTES - 1)];
+    let n = history.len() + 1;
+    if n < MIN_POINTS {
+        return None;
+    }
+    let points = core::iter::once(current)
+        .chain(history.iter().copied())
+        .enumerate()
+        .map(|(i, ppm)| (-(i as f32), ppm as f32));
+    let (mut sum_t, mut sum_c, mut sum_tt, mut sum_tc) = (0.0, 0.0, 0.0, 0.0);
+    for (t, c) in points {
+        sum_t += t;
+        sum_c += c;
+        sum_tt += t * t;
+        sum_tc += t * c;
+    }
+    let n = n as f32;
+    let denominator = n * sum_tt - sum_t * sum_t;
+    Some((n * sum_tc - sum_t * sum_c) / denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, trend::TrendTracker};
+
+    const MEETING_ROOM: &str = include_str!("../../fixtures/meeting_room.csv");
+    // 记录中首次达到 1000 ppm 的分钟
+    const CROSSING_MINUTE: i32 = 43;
+    // 有人进入的分钟，之后 FIT_MINUTES 内拟合窗口里还有空房间的读数
+    const ENTRY_MINUTE: i32 = 5;
+
+    // 逐分钟回放记录，返回 (分钟, 浓度, 预测)
+    fn replay() -> impl Iterator<Item = (i32, u16, Option<u16>)> {
+        let mut tracker = TrendTracker::new();
+        testing::rows::<2>(MEETING_ROOM).map(move |[minute, ppm]| {
+            let averages = tracker.push(minute as u32 * 60, ppm as u16);
+            let due = minutes_until(averages.avg_1min, tracker.history(), 1000);
+            (minute, ppm as u16, due)
+        })
+    }
+
+    #[test]
+    fn empty_room_needs_no_ventilation() {
+        for (minute, _, due) in replay().take_while(|&(minute, ..)| minute < ENTRY_MINUTE) {
+            assert_eq!(due, None, "minute {minute}");
+        }
+    }
+
+    #[test]
+    fn predicts_crossing_of_occupied_room() {
+        let fitted = ENTRY_MINUTE + FIT_MINUTES as i32;
+        for (minute, _, due) in
+            replay().filter(|&(minute, ..)| (fitted..CROSSING_MINUTE).contains(&minute))
+        {
+            let remaining = CROSSING_MINUTE - minute;
+            let due = due.expect("rising room has a prediction") as i32;
+            // 浓度趋于饱和，直线外推只会偏早，不会晚于实际
+            assert!(due <= remaining + 1, "minute {minute}: {due} > {remaining}");
+            assert!(
+                3 * due >= 2 * remaining,
+                "minute {minute}: {due} << {remaining}"
+            );
+        }
+    }
+
+    #[test]
+    fn due_while_above_threshold() {
+        for (minute, ppm, due) in replay().filter(|&(minute, ..)| minute >= CROSSING_MINUTE) {
+            if ppm >= 1000 {
+                assert_eq!(due, Some(0), "minute {minute}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_prediction_while_decaying() {
+        let (minute, _, due) = replay().last().unwrap();
+        assert_eq!(due, None, "minute {minute}");
+        assert!(
+            replay()
+                .filter(|&(minute, ..)| minute >= 82)
+                .all(|(_, _, due)| due.is_none())
+        );
+    }
+
+    #[test]
+    fn linear_rise() {
+        // 每分钟上升 20 ppm，还差 200 ppm
+        assert_eq!(minutes_until(800, &[780, 760, 740, 720], 1000), Some(10));
+        // 向上取整
+        assert_eq!(minutes_until(810, &[790, 770], 1000), Some(10));
+        assert_eq!(minutes_until(801, &[781, 761], 1000), Some(10));
+    }
+
+    #[test]
+    fn already_at_threshold() {
+        assert_eq!(minutes_until(1000, &[], 1000), Some(0));
+        assert_eq!(minutes_until(1200, &[1300, 1400], 1000), Some(0));
+    }
+
+    #[test]
+    fn needs_three_points() {
+        assert_eq!(minutes_until(800, &[], 1000), None);
+        assert_eq!(minutes_until(800, &[700], 1000), None);
+        assert!(minutes_until(800, &[700, 600], 1000).is_some());
+    }
+
+    #[test]
+    fn flat_or_slow_rise_is_not_due() {
+        assert_eq!(minutes_until(800, &[800, 800, 800], 1000), None);
+        assert_eq!(minutes_until(800, &[799, 799, 798, 798], 1000), None);
+        assert_eq!(minutes_until(800, &[820, 840, 860], 1000), None);
+    }
+
+    #[test]
+    fn far_predictions_are_dropped() {
+        // 每分钟 1 ppm，需要 600 分钟
+        assert_eq!(minutes_until(400, &[399, 398, 397], 1000), None);
+        // 每分钟 2 ppm，需要 300 分钟
+        assert_eq!(minutes_until(400, &[398, 396, 394], 1000), Some(300));
+    }
+
+    #[test]
+    fn only_recent_minutes_are_fitted() {
+        // 十分钟之前的平坦历史不影响斜率
+        let mut history = [0u16; 20];
+        for (i, ppm) in history.iter_mut().enumerate() {
+            *ppm = if i < 9 {
+                800 - 20 * (i as u16 + 1)
+            } else {
+                500
+            };
+        }
+        assert_eq!(minutes_until(800, &history, 1000), Some(10));
+    }
+}
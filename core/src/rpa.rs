@@ -0,0 +1,106 @@
+//! Resolvable private addresses, Core Spec Vol 6 Part B 1.3.2.2.
+//!
+//! Keys and blocks are big-endian, most significant octet first, as in the
+//! Security Manager functions of Core Spec Vol 3 Part H 2.2.
+
+use aes::{
+    Aes128,
+    cipher::{BlockEncrypt, KeyInit},
+};
+
+/// Security function `e`, AES-128 of one block
+pub fn encrypt(key: &[u8; 16], block: [u8; 16]) -> [u8; 16] {
+    let mut block = block.into();
+    Aes128::new(key.into()).encrypt_block(&mut block);
+    block.into()
+}
+
+/// Diversifying function d1(k, d, r) = e(k, padding || r || d), Vol 3 Part H 2.2.7
+fn d1(key: &[u8; 16], d: u16, r: u16) -> [u8; 16] {
+    let mut block = [0u8; 16];
+    block[12..14].copy_from_slice(&r.to_be_bytes());
+    block[14..].copy_from_slice(&d.to_be_bytes());
+    encrypt(key, block)
+}
+
+/// IRK derived from the identity root, IRK = d1(IR, 1, 0), Vol 3 Part H Appendix B.2.1
+pub fn identity_resolving_key(ir: &[u8; 16]) -> [u8; 16] {
+    d1(ir, 1, 0)
+}
+
+/// Random address hash ah(k, r) = e(k, padding || r) mod 2^24, Vol 3 Part H 2.2.2
+fn ah(irk: &[u8; 16], prand: u32) -> u32 {
+    let mut block = [0u8; 16];
+    block[13..].copy_from_slice(&prand.to_be_bytes()[1..]);
+    let block = encrypt(irk, block);
+    u32::from_be_bytes([0, block[13], block[14], block[15]])
+}
+
+/// Address bytes, least significant first, for a random 22-bit `prand`
+pub fn address(irk: &[u8; 16], random: u32) -> [u8; 6] {
+    // prand 的最高两位为 0b01
+    let prand = (random & 0x003F_FFFF) | 0x0040_0000;
+    let hash = ah(irk, prand);
+    let mut addr = [0u8; 6];
+    addr[..3].copy_from_slice(&hash.to_le_bytes()[..3]);
+    addr[3..].copy_from_slice(&prand.to_le_bytes()[..3]);
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Core Spec Vol 3 Part H Appendix D.7 的示例数据
+    const IRK: [u8; 16] = [
+        0xec, 0x02, 0x34, 0xa3, 0x57, 0xc8, 0xad, 0x05, 0x34, 0x10, 0x10, 0xa6, 0x0a, 0x39, 0x7d,
+        0x9b,
+    ];
+
+    // 中心设备解析地址的方法
+    fn resolves(irk: &[u8; 16], addr: &[u8; 6]) -> bool {
+        let hash = u32::from_le_bytes([addr[0], addr[1], addr[2], 0]);
+        let prand = u32::from_le_bytes([addr[3], addr[4], addr[5], 0]);
+        ah(irk, prand) == hash
+    }
+
+    #[test]
+    fn ah_sample_data() {
+        assert_eq!(ah(&IRK, 0x708194), 0x0dfbaa);
+    }
+
+    #[test]
+    fn irk_is_d1_of_the_identity_root() {
+        let ir = [0x5Au8; 16];
+        let mut block = [0u8; 16];
+        block[15] = 1;
+        let irk = identity_resolving_key(&ir);
+        assert_eq!(irk, encrypt(&ir, block));
+        assert_eq!(irk, d1(&ir, 1, 0));
+        assert_ne!(irk, ir);
+        // d 和 r 各占 16 位
+        assert_ne!(d1(&ir, 0, 1), irk);
+        assert_ne!(d1(&ir, 0x0100, 0), irk);
+    }
+
+    #[test]
+    fn address_resolves_with_its_irk_only() {
+        let other = identity_resolving_key(&IRK);
+        for random in [0, 0x12_3456, 0xFFFF_FFFF] {
+            let addr = address(&IRK, random);
+            // 最高两位 0b01 表示可解析私有地址
+            assert_eq!(addr[5] >> 6, 0b01);
+            assert!(resolves(&IRK, &addr));
+            assert!(!resolves(&other, &addr));
+        }
+    }
+
+    #[test]
+    fn address_layout() {
+        // hash 在低 24 位，prand 在高 24 位
+        assert_eq!(
+            address(&IRK, 0x708194),
+            [0xaa, 0xfb, 0x0d, 0x94, 0x81, 0x70]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+mod die;
+mod mock;
+mod sim;
+
+pub use die::DieTemperature;
+pub use mock::MockSensor;
+pub use sim::{Pattern, SimulatedSensor};
+
+use crate::filter::FilterConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Measurement {
+    pub co2: u16,
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Variant {
+    Scd40,
+    Scd41,
+    Scd43,
+    Scd30,
+    Mock,
+    Simulated,
+}
+
+impl Variant {
+    /// Output range and warm-up of the sensor for validating its readings
+    pub fn filter_config(self) -> FilterConfig {
+        match self {
+            Self::Scd30 => FilterConfig {
+                max_ppm: 10_000,
+                ..FilterConfig::default()
+            },
+            // 模拟读数没有启动时的异常值
+            Self::Mock | Self::Simulated => FilterConfig {
+                warm_up: 1,
+                ..FilterConfig::default()
+            },
+            Self::Scd40 | Self::Scd41 | Self::Scd43 => FilterConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SensorError {
+    /// The driver reported an error, already logged
+    Driver,
+    NotDetected,
+}
+
+/// A CO2 sensor measuring periodically, the rest of the firmware only sees this
+// 固件只有单线程执行器，不需要约束 future 为 Send
+#[allow(async_fn_in_trait)]
+pub trait Co2Sensor {
+    fn variant(&self) -> Option<Variant>;
+
+    async fn start(&mut self) -> Result<(), SensorError>;
+
+    async fn stop(&mut self) -> Result<(), SensorError>;
+
+    /// Whether a new measurement is ready to be read
+    async fn data_ready(&mut self) -> Result<bool, SensorError>;
+
+    async fn read(&mut self) -> Result<Measurement, SensorError>;
+
+    /// Forced recalibration to a known concentration, measuring continues afterwards
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError>;
+}
+
+/// The new measurement if one is ready
+pub async fn poll<S: Co2Sensor>(sensor: &mut S) -> Result<Option<Measurement>, SensorError> {
+    if sensor.data_ready().await? {
+        sensor.read().await.map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        air_quality::{AirQuality, AirQualityConfig, Classifier},
+        filter::{Filtered, ValidityFilter},
+        testing::block_on,
+    };
+
+    const fn co2(co2: u16) -> Measurement {
+        Measurement {
+            co2,
+            temperature: 21.0,
+            humidity: 40.0,
+        }
+    }
+
+    #[test]
+    fn nothing_ready_before_start() {
+        let mut sensor = MockSensor::default();
+        assert_eq!(block_on(poll(&mut sensor)), Ok(None));
+        assert_eq!(block_on(sensor.read()), Err(SensorError::Driver));
+    }
+
+    #[test]
+    fn replays_script_in_a_loop() {
+        const SCRIPT: [Measurement; 2] = [co2(500), co2(600)];
+        let mut sensor = MockSensor::new(&SCRIPT);
+        block_on(sensor.start()).unwrap();
+        assert!(sensor.running());
+        let readings: [u16; 5] =
+            core::array::from_fn(|_| block_on(poll(&mut sensor)).unwrap().unwrap().co2);
+        assert_eq!(readings, [500, 600, 500, 600, 500]);
+
+        block_on(sensor.stop()).unwrap();
+        assert_eq!(block_on(poll(&mut sensor)), Ok(None));
+    }
+
+    #[test]
+    fn injected_errors_fail_one_call() {
+        let mut sensor = MockSensor::default();
+        block_on(sensor.start()).unwrap();
+        sensor.fail_next(SensorError::Driver);
+        assert_eq!(block_on(poll(&mut sensor)), Err(SensorError::Driver));
+        assert_eq!(block_on(poll(&mut sensor)).unwrap().unwrap().co2, 450);
+
+        sensor.fail_next(SensorError::NotDetected);
+        assert_eq!(block_on(sensor.start()), Err(SensorError::NotDetected));
+        assert_eq!(sensor.starts(), 1);
+    }
+
+    #[test]
+    fn records_calibration() {
+        let mut sensor = MockSensor::default();
+        assert_eq!(sensor.calibrated_to(), None);
+        block_on(sensor.calibrate(420)).unwrap();
+        assert_eq!(sensor.calibrated_to(), Some(420));
+    }
+
+    #[test]
+    fn filter_config_per_variant() {
+        assert_eq!(Variant::Scd30.filter_config().max_ppm, 10_000);
+        assert_eq!(Variant::Scd41.filter_config(), FilterConfig::default());
+        assert_eq!(Variant::Mock.filter_config().warm_up, 1);
+    }
+
+    #[test]
+    fn readings_through_filter_and_classifier() {
+        // 启动后的异常值、传感器的 0 读数和单次尖峰都不应影响分级
+        const SCRIPT: [Measurement; 9] = [
+            co2(9000),
+            co2(700),
+            co2(0),
+            co2(750),
+            co2(5000),
+            co2(800),
+            co2(1100),
+            co2(1200),
+            co2(1250),
+        ];
+        let mut sensor = MockSensor::new(&SCRIPT);
+        block_on(sensor.start()).unwrap();
+        let mut filter = ValidityFilter::new(sensor.variant().unwrap().filter_config());
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        let mut qualities = [None; 9];
+        for quality in &mut qualities {
+            let measurement = block_on(poll(&mut sensor)).unwrap().unwrap();
+            if let Filtered::Valid(co2) = filter.push(measurement.co2) {
+                *quality = Some(classifier.update(co2).quality);
+            }
+        }
+        use AirQuality::*;
+        assert_eq!(
+            qualities,
+            [
+                None,
+                Some(Good),
+                None,
+                Some(Good),
+                Some(Good),
+                Some(Moderate),
+                Some(Poor),
+                Some(Poor),
+                Some(Poor),
+            ]
+        );
+    }
+}
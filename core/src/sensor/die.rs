@@ -0,0 +1,105 @@
+/// Ambient temperature estimate from the nRF52 die temperature, which runs
+/// warmer than the room.
+///
+/// The offset is learned while the SCD4x agrees, during the first
+/// `LEARN_SAMPLES` agreeing readings only, and stays within a plausible
+/// self-heating range, so a slowly drifting SCD4x can't pull the reference
+/// along with it.
+#[derive(Debug)]
+pub struct DieTemperature {
+    offset: f32,
+    learned: u16,
+}
+
+impl Default for DieTemperature {
+    fn default() -> Self {
+        Self {
+            offset: Self::SELF_HEATING_C,
+            learned: 0,
+        }
+    }
+}
+
+impl DieTemperature {
+    // 未学习时假定的芯片自热温升
+    const SELF_HEATING_C: f32 = 3.0;
+    // 学习到的温升范围
+    const MIN_OFFSET_C: f32 = 0.0;
+    const MAX_OFFSET_C: f32 = 6.0;
+    // 与 SCD4x 相差超过该值视为传感器异常
+    const DIVERGENCE_C: f32 = 8.0;
+    const LEARN_ALPHA: f32 = 0.05;
+    /// Agreeing readings used for learning, about 10 minutes at the SCD4x's 5 s interval
+    pub const LEARN_SAMPLES: u16 = 120;
+
+    pub fn ambient(&self, die: f32) -> f32 {
+        die - self.offset
+    }
+
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Whether the offset is frozen
+    pub fn is_calibrated(&self) -> bool {
+        self.learned >= Self::LEARN_SAMPLES
+    }
+
+    /// Compare a SCD4x reading with the die, returns true when they diverge.
+    /// Agreeing readings refine the self-heating offset until calibrated.
+    pub fn check(&mut self, die: f32, ambient: f32) -> bool {
+        let diverges = (self.ambient(die) - ambient).abs() > Self::DIVERGENCE_C;
+        if !diverges && !self.is_calibrated() {
+            self.offset += (die - ambient - self.offset) * Self::LEARN_ALPHA;
+            self.offset = self.offset.clamp(Self::MIN_OFFSET_C, Self::MAX_OFFSET_C);
+            self.learned += 1;
+        }
+        diverges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learns_offset_then_freezes() {
+        let mut die = DieTemperature::default();
+        for _ in 0..DieTemperature::LEARN_SAMPLES {
+            assert!(!die.check(25.0, 21.0));
+        }
+        assert!(die.is_calibrated());
+        assert!((die.offset() - 4.0).abs() < 0.01);
+
+        // 校准后 SCD4x 慢慢漂移，偏移保持不变
+        for i in 0..1000 {
+            die.check(25.0, 21.0 - i as f32 * 0.005);
+        }
+        assert!((die.offset() - 4.0).abs() < 0.01);
+        assert!((die.ambient(25.0) - 21.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn divergent_readings_are_flagged_and_not_learned() {
+        let mut die = DieTemperature::default();
+        assert!(die.check(25.0, 10.0));
+        assert_eq!(die.offset(), DieTemperature::SELF_HEATING_C);
+        assert!(!die.is_calibrated());
+    }
+
+    #[test]
+    fn offset_stays_in_self_heating_range() {
+        let mut die = DieTemperature::default();
+        // 差值在报警范围内但超出合理温升
+        for _ in 0..DieTemperature::LEARN_SAMPLES {
+            assert!(!die.check(30.0, 22.0));
+        }
+        assert_eq!(die.offset(), DieTemperature::MAX_OFFSET_C);
+
+        let mut die = DieTemperature::default();
+        for _ in 0..DieTemperature::LEARN_SAMPLES {
+            die.check(20.0, 22.0);
+        }
+        assert_eq!(die.offset(), DieTemperature::MIN_OFFSET_C);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Co2Sensor, Measurement, SensorError, Variant};
+
+// 依次循环返回的读数
+const SCRIPT: [Measurement; 6] = [
+    Measurement {
+        co2: 450,
+        temperature: 21.5,
+        humidity: 40.0,
+    },
+    Measurement {
+        co2: 700,
+        temperature: 22.0,
+        humidity: 42.0,
+    },
+    Measurement {
+        co2: 950,
+        temperature: 22.5,
+        humidity: 45.0,
+    },
+    Measurement {
+        co2: 1250,
+        temperature: 23.0,
+        humidity: 48.0,
+    },
+    Measurement {
+        co2: 2100,
+        temperature: 23.5,
+        humidity: 50.0,
+    },
+    Measurement {
+        co2: 600,
+        temperature: 21.0,
+        humidity: 41.0,
+    },
+];
+
+/// Replays a fixed script of measurements, one per `read`, and records what
+/// it was asked to do so tests can check the code driving it
+pub struct MockSensor {
+    script: &'static [Measurement],
+    index: usize,
+    running: bool,
+    fail_next: Option<SensorError>,
+    starts: u32,
+    calibrated_to: Option<u16>,
+}
+
+impl Default for MockSensor {
+    fn default() -> Self {
+        Self::new(&SCRIPT)
+    }
+}
+
+impl MockSensor {
+    pub const fn new(script: &'static [Measurement]) -> Self {
+        Self {
+            script,
+            index: 0,
+            running: false,
+            fail_next: None,
+            starts: 0,
+            calibrated_to: None,
+        }
+    }
+
+    /// The next call fails with `error`
+    pub fn fail_next(&mut self, error: SensorError) {
+        self.fail_next = Some(error);
+    }
+
+    pub const fn running(&self) -> bool {
+        self.running
+    }
+
+    /// How often measuring was started
+    pub const fn starts(&self) -> u32 {
+        self.starts
+    }
+
+    /// Target of the last forced recalibration
+    pub const fn calibrated_to(&self) -> Option<u16> {
+        self.calibrated_to
+    }
+
+    fn check(&mut self) -> Result<(), SensorError> {
+        self.fail_next.take().map_or(Ok(()), Err)
+    }
+}
+
+impl Co2Sensor for MockSensor {
+    fn variant(&self) -> Option<Variant> {
+        Some(Variant::Mock)
+    }
+
+    async fn start(&mut self) -> Result<(), SensorError> {
+        self.check()?;
+        self.running = true;
+        self.starts += 1;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), SensorError> {
+        self.check()?;
+        self.running = false;
+        Ok(())
+    }
+
+    async fn data_ready(&mut self) -> Result<bool, SensorError> {
+        self.check()?;
+        Ok(self.running && !self.script.is_empty())
+    }
+
+    async fn read(&mut self) -> Result<Measurement, SensorError> {
+        self.check()?;
+        if !self.running || self.script.is_empty() {
+            return Err(SensorError::Driver);
+        }
+        let measurement = self.script[self.index % self.script.len()];
+        self.index += 1;
+        Ok(measurement)
+    }
+
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError> {
+        self.check()?;
+        self.calibrated_to = Some(target_ppm);
+        Ok(())
+    }
+}
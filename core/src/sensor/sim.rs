@@ -0,0 +1,343 @@
+use super::{Co2Sensor, Measurement, SensorError, Variant};
+
+/// How the simulated readings evolve, one step per measurement
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    /// Replay the measurements in order, then start over
+    Script(&'static [Measurement]),
+    /// A room that is occupied for `occupied` measurements and then empty for
+    /// `empty`, CO2, temperature and humidity rise and decay towards the
+    /// levels of each phase
+    Occupancy { occupied: u32, empty: u32 },
+}
+
+/// Deterministic sensor for running the firmware without hardware and for
+/// exercising the processing pipeline in host tests.
+///
+/// A measurement becomes ready every `polls_per_measurement` calls to
+/// `data_ready`, errors can be injected periodically or for the next polls.
+pub struct SimulatedSensor {
+    pattern: Pattern,
+    polls_per_measurement: u32,
+    fault_every: Option<u32>,
+    fail_next: u32,
+    running: bool,
+    polls: u32,
+    step: u32,
+    current: Measurement,
+    // 强制校准后叠加到 CO2 上的偏移
+    co2_offset: i32,
+}
+
+impl Default for SimulatedSensor {
+    // 约 2 分钟有人、3 分钟无人，偶尔读取失败
+    fn default() -> Self {
+        Self::new(Pattern::Occupancy {
+            occupied: 24,
+            empty: 36,
+        })
+        .with_fault_every(97)
+    }
+}
+
+impl SimulatedSensor {
+    // 室外新风水平
+    const OUTDOOR: Measurement = Measurement {
+        co2: 420,
+        temperature: 21.0,
+        humidity: 40.0,
+    };
+    const OCCUPIED: Measurement = Measurement {
+        co2: 2400,
+        temperature: 23.5,
+        humidity: 55.0,
+    };
+    // 每次测量向目标靠近的比例
+    const RISE: f32 = 0.04;
+    const DECAY: f32 = 0.06;
+
+    pub const fn new(pattern: Pattern) -> Self {
+        Self {
+            pattern,
+            // SCD4x 每 5 秒一个读数，sense_task 每秒查询一次
+            polls_per_measurement: 5,
+            fault_every: None,
+            fail_next: 0,
+            running: false,
+            polls: 0,
+            step: 0,
+            current: Self::OUTDOOR,
+            co2_offset: 0,
+        }
+    }
+
+    pub const fn with_polls_per_measurement(mut self, polls: u32) -> Self {
+        self.polls_per_measurement = if polls == 0 { 1 } else { polls };
+        self
+    }
+
+    /// Every `polls`-th call to `data_ready` fails
+    pub const fn with_fault_every(mut self, polls: u32) -> Self {
+        self.fault_every = if polls == 0 { None } else { Some(polls) };
+        self
+    }
+
+    /// The next `count` calls to `data_ready` fail
+    pub fn fail_next(&mut self, count: u32) {
+        self.fail_next = count;
+    }
+
+    fn next_measurement(&mut self) -> Measurement {
+        let step = self.step;
+        self.step = self.step.wrapping_add(1);
+        match self.pattern {
+            Pattern::Script(script) => {
+                if let Some(&measurement) = script.get(step as usize % script.len().max(1)) {
+                    self.current = measurement;
+                }
+                self.current
+            }
+            Pattern::Occupancy { occupied, empty } => {
+                let period = (occupied + empty).max(1);
+                let (target, rate) = if step % period < occupied {
+                    (Self::OCCUPIED, Self::RISE)
+                } else {
+                    (Self::OUTDOOR, Self::DECAY)
+                };
+                let approach = |current: f32, target: f32| current + (target - current) * rate;
+                let current = self.current;
+                self.current = Measurement {
+                    co2: approach(current.co2 as f32, target.co2 as f32) as u16,
+                    temperature: approach(current.temperature, target.temperature),
+                    humidity: approach(current.humidity, target.humidity),
+                };
+                self.current
+            }
+        }
+    }
+}
+
+impl Co2Sensor for SimulatedSensor {
+    fn variant(&self) -> Option<Variant> {
+        Some(Variant::Simulated)
+    }
+
+    async fn start(&mut self) -> Result<(), SensorError> {
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), SensorError> {
+        self.running = false;
+        Ok(())
+    }
+
+    async fn data_ready(&mut self) -> Result<bool, SensorError> {
+        self.polls = self.polls.wrapping_add(1);
+        if self.fail_next > 0 {
+            self.fail_next -= 1;
+            return Err(SensorError::Driver);
+        }
+        if self
+            .fault_every
+            .is_some_and(|every| self.polls.is_multiple_of(every))
+        {
+            return Err(SensorError::Driver);
+        }
+        Ok(self.running && self.polls.is_multiple_of(self.polls_per_measurement))
+    }
+
+    async fn read(&mut self) -> Result<Measurement, SensorError> {
+        if !self.running {
+            return Err(SensorError::Driver);
+        }
+        let mut measurement = self.next_measurement();
+        measurement.co2 =
+            (measurement.co2 as i32 + self.co2_offset).clamp(0, u16::MAX as i32) as u16;
+        Ok(measurement)
+    }
+
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError> {
+        // 让当前读数等于目标浓度
+        self.co2_offset = target_ppm as i32 - self.current.co2 as i32;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advertising::AdvStatus,
+        air_quality::{AirQuality, AirQualityConfig, Classifier},
+        alarm::{Alarm, AlarmConfig, AlarmState},
+        display::mode::{DisplayMode, ModeManager},
+        filter::{Filtered, ValidityFilter},
+        format::{self, Metric},
+        sensor::poll,
+        testing::block_on,
+        trend::{Trend, TrendTracker},
+    };
+
+    #[test]
+    fn measurement_ready_every_few_polls() {
+        let mut sensor = SimulatedSensor::new(Pattern::Script(&[])).with_polls_per_measurement(3);
+        assert_eq!(block_on(sensor.data_ready()), Ok(false));
+        block_on(sensor.start()).unwrap();
+        let ready: [bool; 6] = core::array::from_fn(|_| block_on(sensor.data_ready()).unwrap());
+        assert_eq!(ready, [false, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn injected_faults() {
+        let mut sensor = SimulatedSensor::new(Pattern::Script(&[]))
+            .with_polls_per_measurement(1)
+            .with_fault_every(4);
+        block_on(sensor.start()).unwrap();
+        let ok: [bool; 8] = core::array::from_fn(|_| block_on(sensor.data_ready()).is_ok());
+        assert_eq!(ok, [true, true, true, false, true, true, true, false]);
+
+        sensor.fail_next(2);
+        assert_eq!(block_on(sensor.data_ready()), Err(SensorError::Driver));
+        assert_eq!(block_on(sensor.data_ready()), Err(SensorError::Driver));
+        assert_eq!(block_on(sensor.data_ready()), Ok(true));
+    }
+
+    #[test]
+    fn calibration_shifts_readings() {
+        const SCRIPT: [Measurement; 1] = [Measurement {
+            co2: 500,
+            temperature: 21.0,
+            humidity: 40.0,
+        }];
+        let mut sensor = SimulatedSensor::new(Pattern::Script(&SCRIPT));
+        block_on(sensor.start()).unwrap();
+        assert_eq!(block_on(sensor.read()).unwrap().co2, 500);
+        block_on(sensor.calibrate(420)).unwrap();
+        assert_eq!(block_on(sensor.read()).unwrap().co2, 420);
+    }
+
+    #[test]
+    fn occupancy_drives_trend() {
+        // 每秒查询一次，5 秒一个读数，有人和无人各 10 分钟
+        let mut sensor = SimulatedSensor::new(Pattern::Occupancy {
+            occupied: 120,
+            empty: 120,
+        });
+        block_on(sensor.start()).unwrap();
+        let mut filter = ValidityFilter::new(sensor.variant().unwrap().filter_config());
+        let mut tracker = TrendTracker::new();
+        let mut trends = [None; 4];
+        let mut measurements = 0;
+        for now_s in 0..20 * 60 {
+            let Some(measurement) = block_on(poll(&mut sensor)).unwrap() else {
+                continue;
+            };
+            measurements += 1;
+            if let Filtered::Valid(co2) = filter.push(measurement.co2) {
+                let averages = tracker.push(now_s, co2);
+                // 有人和无人阶段的中间和末尾
+                if let Some(i) = [60, 120, 180, 240].iter().position(|&m| m == measurements) {
+                    trends[i] = Some((averages.trend, co2));
+                }
+            }
+        }
+        let [(rising, high), (_, peak), (falling, low), (_, outdoor)] = trends.map(Option::unwrap);
+        assert_eq!(rising, Trend::Rising);
+        assert_eq!(falling, Trend::Falling);
+        assert!(high > 2000 && peak > high, "{high} {peak}");
+        assert!(low < 1000 && outdoor < low, "{low} {outdoor}");
+        assert!((420..450).contains(&outdoor));
+    }
+
+    #[test]
+    fn occupancy_drives_alarm_display_and_advertising() {
+        // 有人和无人各 10 分钟，与固件一样每秒查询传感器并更新报警
+        let mut sensor = SimulatedSensor::new(Pattern::Occupancy {
+            occupied: 120,
+            empty: 120,
+        });
+        block_on(sensor.start()).unwrap();
+        let mut filter = ValidityFilter::new(sensor.variant().unwrap().filter_config());
+        let mut classifier = Classifier::new(AirQualityConfig::default());
+        let mut alarm = Alarm::new(AlarmConfig::default());
+        let mut modes = ModeManager::default();
+        // 通过 BLE 切换到只显示等级条
+        modes.set(DisplayMode::try_from(4).unwrap());
+
+        // 短按 A 键：报警响起时贪睡，否则切换显示模式
+        let press_a = |alarm: &mut Alarm, modes: &mut ModeManager, now_ms| {
+            if !alarm.snooze(now_ms) {
+                modes.next();
+            }
+        };
+
+        let mut transitions = heapless::Vec::<(u32, AlarmState), 8>::new();
+        let mut co2 = 0;
+        let mut status = classifier.status();
+        let mut max_bar = 0;
+        let mut sounding_flags = None;
+        for now_s in 0..20 * 60 {
+            let now_ms = now_s as u64 * 1000;
+            if let Some(measurement) = block_on(poll(&mut sensor)).unwrap()
+                && let Filtered::Valid(value) = filter.push(measurement.co2)
+            {
+                co2 = value;
+                status = classifier.update(co2);
+                alarm.observe(status);
+            }
+            let state = alarm.update(now_ms, None);
+            if transitions.last().is_none_or(|&(_, last)| last != state) {
+                transitions.push((now_s, state)).unwrap();
+                if state == AlarmState::Sounding && sounding_flags.is_none() {
+                    sounding_flags = Some((
+                        co2,
+                        AdvStatus {
+                            air_quality: Some(status),
+                            ventilation_due: None,
+                        }
+                        .payload()[1],
+                    ));
+                    press_a(&mut alarm, &mut modes, now_ms);
+                    transitions.push((now_s, alarm.state())).unwrap();
+                }
+            }
+            if modes.mode() == DisplayMode::BarOnly {
+                max_bar = max_bar.max(status.bar_level());
+            }
+        }
+
+        // 进入较差等级时响起，贪睡到期后仍超标再次响起，空气恢复后停止
+        let states = transitions.iter().map(|&(_, state)| state);
+        assert!(states.eq([
+            AlarmState::Idle,
+            AlarmState::Sounding,
+            AlarmState::Snoozed,
+            AlarmState::Sounding,
+            AlarmState::Idle,
+        ]));
+        let (first, _) = transitions[1];
+        let (snoozed, _) = transitions[2];
+        let (again, _) = transitions[3];
+        let (recovered, _) = transitions[4];
+        assert_eq!(snoozed, first);
+        assert_eq!(again, first + 10 * 60, "{transitions:?}");
+        // 10 分钟后开始无人，读数需要降到迟滞以下
+        assert!(recovered > 10 * 60 && recovered > again, "{recovered}");
+
+        let (sounding_co2, flags) = sounding_flags.unwrap();
+        assert!((1000..1400).contains(&sounding_co2), "{sounding_co2}");
+        assert_eq!(flags, 0x80 | AirQuality::Poor as u8);
+
+        // 贪睡没有切换模式，峰值时等级条亮满第五行
+        assert_eq!(max_bar, 5);
+        assert_eq!(status.bar_level(), 1);
+        assert_eq!(status.flags(), 0);
+
+        press_a(&mut alarm, &mut modes, 20 * 60 * 1000);
+        assert_eq!(modes.mode(), DisplayMode::Co2);
+        let text = format::reading_text(Metric::Co2, co2 as i32);
+        assert_eq!(text.trim().parse(), Ok(co2));
+        assert!((420..450).contains(&co2), "{co2}");
+    }
+}
@@ -0,0 +1,27 @@
+use core::{
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// Rows of a CSV fixture as numbers, skipping `#` comments and the header line
+pub fn rows<const N: usize>(csv: &'static str) -> impl Iterator<Item = [i32; N]> {
+    csv.lines()
+        .filter(|line| !line.starts_with('#'))
+        .skip(1)
+        .map(|line| {
+            let mut fields = line.split(',').map(|field| field.trim().parse().unwrap());
+            core::array::from_fn(|_| fields.next().unwrap())
+        })
+}
+
+/// Run a future that never waits on anything external, such as the mock and
+/// simulated sensors
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,339 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trend {
+    Falling,
+    Stable,
+    Rising,
+}
+
+impl Trend {
+    // 变化率超过该值 (ppm/分钟) 才算上升或下降
+    const THRESHOLD_PPM_PER_MIN: i16 = 10;
+
+    pub const fn from_rate(rate_ppm_per_min: i16) -> Self {
+        if rate_ppm_per_min >= Self::THRESHOLD_PPM_PER_MIN {
+            Self::Rising
+        } else if rate_ppm_per_min <= -Self::THRESHOLD_PPM_PER_MIN {
+            Self::Falling
+        } else {
+            Self::Stable
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Averages {
+    /// Readings of the last 60 s
+    pub avg_1min: u16,
+    /// Readings of the last 5 minutes
+    pub avg_5min: u16,
+    /// Readings of the last hour
+    pub avg_1h: u16,
+    /// Change of the one-minute average over the last five minutes
+    pub rate_ppm_per_min: i16,
+    pub trend: Trend,
+}
+
+// 一小时内保留的读数，SCD4x 每 5 秒一个
+const SPACING_S: u32 = 5;
+const HOUR_S: u32 = 3600;
+const READINGS: usize = (HOUR_S / SPACING_S) as usize;
+
+/// Rolling CO2 averages over the last minute, five minutes and hour.
+///
+/// Keeps the readings of the last hour, at most one per `SPACING_S`, so the
+/// windows slide with every reading. Readings closer than that to the previous
+/// kept one are left out. Completed calendar minutes are also kept as their
+/// averages for `history`.
+pub struct TrendTracker {
+    // 读数环形缓冲，next 为下一个写入位置
+    times: [u32; READINGS],
+    values: [u16; READINGS],
+    next: usize,
+    stored: usize,
+    now_s: u32,
+    minute: u32,
+    sum: u32,
+    count: u32,
+    // 最近 60 个整分钟的平均值，[0] 为最近一分钟
+    minutes: [u16; 60],
+    len: usize,
+}
+
+impl Default for TrendTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrendTracker {
+    const RATE_MINUTES: u32 = 5;
+
+    pub const fn new() -> Self {
+        Self {
+            times: [0; READINGS],
+            values: [0; READINGS],
+            next: 0,
+            stored: 0,
+            now_s: 0,
+            minute: 0,
+            sum: 0,
+            count: 0,
+            minutes: [0; 60],
+            len: 0,
+        }
+    }
+
+    /// Add a reading taken `now_s` seconds after start
+    pub fn push(&mut self, now_s: u32, co2_ppm: u16) -> Averages {
+        let minute = now_s / 60;
+        if self.count > 0 && minute != self.minute {
+            // 没有读数的分钟沿用上一分钟的平均值
+            let average = (self.sum / self.count) as u16;
+            let elapsed = (minute.saturating_sub(self.minute) as usize).min(self.minutes.len());
+            for _ in 0..elapsed {
+                self.minutes.rotate_right(1);
+                self.minutes[0] = average;
+            }
+            self.len = (self.len + elapsed).min(self.minutes.len());
+            self.sum = 0;
+            self.count = 0;
+        }
+        self.minute = minute;
+        self.sum += co2_ppm as u32;
+        self.count += 1;
+
+        let spaced = self
+            .newest()
+            .is_none_or(|(time, _)| now_s.wrapping_sub(time) >= SPACING_S);
+        if spaced {
+            self.times[self.next] = now_s;
+            self.values[self.next] = co2_ppm;
+            self.next = (self.next + 1) % READINGS;
+            self.stored = (self.stored + 1).min(READINGS);
+        }
+        self.now_s = now_s;
+        self.averages()
+    }
+
+    /// Averages of the completed minutes, most recent first
+    pub fn history(&self) -> &[u16] {
+        &self.minutes[..self.len]
+    }
+
+    fn newest(&self) -> Option<(u32, u16)> {
+        self.readings().next()
+    }
+
+    // 按从新到旧的顺序给出 (时间, 浓度)
+    fn readings(&self) -> impl Iterator<Item = (u32, u16)> + '_ {
+        (1..=self.stored).map(|back| {
+            let i = (self.next + READINGS - back) % READINGS;
+            (self.times[i], self.values[i])
+        })
+    }
+
+    // 距现在 [from_s, to_s) 秒内读数的平均
+    fn window(&self, from_s: u32, to_s: u32) -> Option<u16> {
+        let (sum, count) = self
+            .readings()
+            .map(|(time, ppm)| (self.now_s.wrapping_sub(time), ppm))
+            .skip_while(|&(age, _)| age < from_s)
+            .take_while(|&(age, _)| age < to_s)
+            .fold((0u32, 0u32), |(sum, count), (_, ppm)| {
+                (sum + ppm as u32, count + 1)
+            });
+        (count > 0).then(|| (sum / count) as u16)
+    }
+
+    pub fn averages(&self) -> Averages {
+        let average = |seconds| self.window(0, seconds).unwrap_or(0);
+        let avg_1min = average(60);
+        // 与五分钟前的一分钟平均比较，历史不足时用最早的整分钟
+        let oldest_s = self
+            .readings()
+            .last()
+            .map_or(0, |(time, _)| self.now_s.wrapping_sub(time));
+        let minutes = (oldest_s / 60).min(Self::RATE_MINUTES);
+        let rate_ppm_per_min = match self.window(minutes * 60, (minutes + 1) * 60) {
+            Some(then) if minutes > 0 => ((avg_1min as i32 - then as i32) / minutes as i32) as i16,
+            _ => 0,
+        };
+        Averages {
+            avg_1min,
+            avg_5min: average(5 * 60),
+            avg_1h: average(HOUR_S),
+            rate_ppm_per_min,
+            trend: Trend::from_rate(rate_ppm_per_min),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每 5 秒一个读数，ppm(t) 给出第 t 秒的浓度，返回最后的结果
+    fn feed(
+        tracker: &mut TrendTracker,
+        seconds: core::ops::Range<u32>,
+        ppm: impl Fn(u32) -> u16,
+    ) -> Averages {
+        let mut averages = None;
+        for t in seconds.step_by(5) {
+            averages = Some(tracker.push(t, ppm(t)));
+        }
+        averages.unwrap()
+    }
+
+    #[test]
+    fn flat_series_is_stable() {
+        let mut tracker = TrendTracker::new();
+        let averages = feed(&mut tracker, 0..3600, |_| 800);
+        assert_eq!(averages.avg_1min, 800);
+        assert_eq!(averages.avg_5min, 800);
+        assert_eq!(averages.avg_1h, 800);
+        assert_eq!(averages.rate_ppm_per_min, 0);
+        assert_eq!(averages.trend, Trend::Stable);
+    }
+
+    #[test]
+    fn rising_series() {
+        let mut tracker = TrendTracker::new();
+        // 每分钟上升 20 ppm
+        let averages = feed(&mut tracker, 0..1200, |t| 500 + (t / 3) as u16);
+        assert_eq!(averages.rate_ppm_per_min, 20);
+        assert_eq!(averages.trend, Trend::Rising);
+        assert!(averages.avg_1min > averages.avg_5min);
+        assert!(averages.avg_5min > averages.avg_1h);
+    }
+
+    #[test]
+    fn falling_series() {
+        let mut tracker = TrendTracker::new();
+        let averages = feed(&mut tracker, 0..1200, |t| 2000 - (t / 2) as u16);
+        assert_eq!(averages.rate_ppm_per_min, -30);
+        assert_eq!(averages.trend, Trend::Falling);
+        assert!(averages.avg_1min < averages.avg_5min);
+    }
+
+    #[test]
+    fn slow_change_is_stable() {
+        let mut tracker = TrendTracker::new();
+        // 每分钟 6 ppm，低于阈值
+        let averages = feed(&mut tracker, 0..1200, |t| 600 + (t / 10) as u16);
+        assert_eq!(averages.trend, Trend::Stable);
+    }
+
+    #[test]
+    fn trend_threshold() {
+        assert_eq!(Trend::from_rate(9), Trend::Stable);
+        assert_eq!(Trend::from_rate(10), Trend::Rising);
+        assert_eq!(Trend::from_rate(-9), Trend::Stable);
+        assert_eq!(Trend::from_rate(-10), Trend::Falling);
+    }
+
+    #[test]
+    fn first_minute_has_no_history() {
+        let mut tracker = TrendTracker::new();
+        let averages = feed(&mut tracker, 0..60, |t| 500 + t as u16);
+        assert!(tracker.history().is_empty());
+        assert_eq!(averages.avg_1min, averages.avg_5min);
+        assert_eq!(averages.avg_1min, averages.avg_1h);
+        assert_eq!(averages.rate_ppm_per_min, 0);
+    }
+
+    #[test]
+    fn minute_completes_on_boundary() {
+        let mut tracker = TrendTracker::new();
+        tracker.push(0, 600);
+        tracker.push(55, 800);
+        assert!(tracker.history().is_empty());
+        let averages = tracker.push(60, 1000);
+        assert_eq!(tracker.history(), &[700]);
+        // 最近 60 秒包含上一分钟末尾的读数
+        assert_eq!(averages.avg_1min, 900);
+        assert_eq!(averages.avg_5min, 800);
+        assert_eq!(averages.rate_ppm_per_min, 300);
+    }
+
+    #[test]
+    fn one_minute_window_slides_across_boundary() {
+        let mut tracker = TrendTracker::new();
+        feed(&mut tracker, 0..60, |_| 600);
+        // 跨过整分钟后逐渐被新读数替换，而不是跳到单个读数
+        let mut last = 600;
+        for (i, t) in (60..120).step_by(5).enumerate() {
+            let averages = tracker.push(t, 1200);
+            let expected = (600 * (11 - i as u32) + 1200 * (i as u32 + 1)) / 12;
+            assert_eq!(averages.avg_1min as u32, expected, "{t} s");
+            assert!(averages.avg_1min > last);
+            last = averages.avg_1min;
+        }
+        assert_eq!(last, 1200);
+    }
+
+    #[test]
+    fn five_minute_and_hour_windows_slide() {
+        let mut tracker = TrendTracker::new();
+        feed(&mut tracker, 0..3600, |_| 600);
+        // 新浓度持续 30 秒，只占各窗口的一部分
+        let averages = feed(&mut tracker, 3600..3630, |_| 1200);
+        assert_eq!(averages.avg_1min, 900);
+        assert_eq!(averages.avg_5min, 660);
+        assert_eq!(averages.avg_1h, 605);
+    }
+
+    #[test]
+    fn close_readings_are_thinned() {
+        let mut tracker = TrendTracker::new();
+        tracker.push(0, 600);
+        // 与上一个保留的读数间隔不足 5 秒
+        let averages = tracker.push(2, 1200);
+        assert_eq!(averages.avg_1min, 600);
+        let averages = tracker.push(5, 1200);
+        assert_eq!(averages.avg_1min, 900);
+    }
+
+    #[test]
+    fn gap_repeats_last_minute() {
+        let mut tracker = TrendTracker::new();
+        tracker.push(0, 600);
+        // 中间 3 分钟没有读数
+        tracker.push(240, 600);
+        assert_eq!(tracker.history(), &[600, 600, 600, 600]);
+    }
+
+    #[test]
+    fn five_minute_window_covers_last_300_seconds() {
+        let mut tracker = TrendTracker::new();
+        for minute in 0..10 {
+            tracker.push(minute * 60, 100 * (minute as u16 + 1));
+        }
+        let averages = tracker.averages();
+        // 300 秒内的读数为 1000、900、800、700、600
+        assert_eq!(averages.avg_5min, 800);
+        // 与五分钟前的 500 比较
+        assert_eq!(averages.rate_ppm_per_min, 100);
+    }
+
+    #[test]
+    fn history_is_capped_at_an_hour() {
+        let mut tracker = TrendTracker::new();
+        feed(&mut tracker, 0..3600, |_| 1000);
+        let averages = feed(&mut tracker, 3600..7200, |_| 500);
+        assert_eq!(tracker.history().len(), 60);
+        assert_eq!(averages.avg_1h, 500);
+    }
+
+    #[test]
+    fn long_gap_fills_whole_history() {
+        let mut tracker = TrendTracker::new();
+        feed(&mut tracker, 0..600, |_| 1000);
+        // 两小时后的读数，整小时的历史都沿用最后一分钟
+        tracker.push(600 + 7200, 1000);
+        assert_eq!(tracker.history().len(), 60);
+        assert!(tracker.history().iter().all(|&ppm| ppm == 1000));
+    }
+}
@@ -0,0 +1,32 @@
+/// Conversion into the BLE time units of a GATT value
+pub trait ToTimeUnits {
+    /// Length of one unit in microseconds
+    const DIVISOR: usize;
+
+    fn from_ms(ms: usize) -> usize {
+        Self::from_us(ms * 1000)
+    }
+
+    fn from_us(us: usize) -> usize {
+        us / Self::DIVISOR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Interval;
+
+    impl ToTimeUnits for Interval {
+        const DIVISOR: usize = 1250;
+    }
+
+    #[test]
+    fn converts_to_units() {
+        assert_eq!(Interval::from_ms(100), 80);
+        assert_eq!(Interval::from_us(7_500), 6);
+        // 不足一个单位时向下取整
+        assert_eq!(Interval::from_us(1_249), 0);
+    }
+}
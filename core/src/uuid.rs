@@ -0,0 +1,80 @@
+// Thingy base UUID:
+// EF68xxxx-9B35-4933-9B10-52FFA9740042
+const THINGY_BASE_UUID: [u8; 16] = [
+    0x42, 0x00, 0x74, 0xA9, 0xFF, 0x52, 0x10, 0x9B, 0x33, 0x49, 0x35, 0x9B, 0x00, 0x00, 0x68, 0xEF,
+];
+
+pub struct ThingyUuid(pub u16);
+
+impl ThingyUuid {
+    pub const fn into_u128(self) -> u128 {
+        u128::from_le_bytes(THINGY_BASE_UUID) | ((self.0 as u128) << 96)
+    }
+}
+
+#[cfg(feature = "trouble")]
+impl From<ThingyUuid> for trouble_host::prelude::Uuid {
+    fn from(value: ThingyUuid) -> Self {
+        value.into_u128().into()
+    }
+}
+
+impl From<ThingyUuid> for [u8; 16] {
+    fn from(value: ThingyUuid) -> Self {
+        value.into_u128().to_le_bytes()
+    }
+}
+
+// microbit_co2 vendor base UUID:
+// C02Bxxxx-1D4E-4A6B-9C1F-6D6963726F62
+const VENDOR_BASE_UUID: [u8; 16] = [
+    0x62, 0x6F, 0x72, 0x63, 0x69, 0x6D, 0x1F, 0x9C, 0x6B, 0x4A, 0x4E, 0x1D, 0x00, 0x00, 0x2B, 0xC0,
+];
+
+pub struct VendorUuid(pub u16);
+
+impl VendorUuid {
+    pub const fn into_u128(self) -> u128 {
+        u128::from_le_bytes(VENDOR_BASE_UUID) | ((self.0 as u128) << 96)
+    }
+}
+
+#[cfg(feature = "trouble")]
+impl From<VendorUuid> for trouble_host::prelude::Uuid {
+    fn from(value: VendorUuid) -> Self {
+        value.into_u128().into()
+    }
+}
+
+impl From<VendorUuid> for [u8; 16] {
+    fn from(value: VendorUuid) -> Self {
+        value.into_u128().to_le_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thingy_uuid() {
+        // EF680201-9B35-4933-9B10-52FFA9740042
+        assert_eq!(
+            ThingyUuid(0x0201).into_u128(),
+            0xEF680201_9B35_4933_9B10_52FFA9740042
+        );
+        let bytes: [u8; 16] = ThingyUuid(0x0201).into();
+        assert_eq!(bytes[12..], [0x01, 0x02, 0x68, 0xEF]);
+    }
+
+    #[test]
+    fn vendor_uuid() {
+        // C02B0101-1D4E-4A6B-9C1F-6D6963726F62
+        assert_eq!(
+            VendorUuid(0x0101).into_u128(),
+            0xC02B0101_1D4E_4A6B_9C1F_6D6963726F62
+        );
+        let bytes: [u8; 16] = VendorUuid(0x0101).into();
+        assert_eq!(bytes[..4], [0x62, 0x6F, 0x72, 0x63]);
+    }
+}
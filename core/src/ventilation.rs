@@ -0,0 +1,216 @@
+/// CO2 driven ventilation output with hysteresis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VentilationConfig {
+    /// Edge-connector output index, `None` disables the automation
+    pub pin: Option<u8>,
+    pub on_ppm: u16,
+    pub off_ppm: u16,
+    /// Once switched on the output stays on at least this long
+    pub min_on_s: u16,
+    /// Once switched off the output stays off at least this long
+    pub min_off_s: u16,
+}
+
+impl Default for VentilationConfig {
+    // 最短开关时间避免风扇或继电器频繁启停
+    fn default() -> Self {
+        Self {
+            pin: None,
+            on_ppm: 1000,
+            off_ppm: 800,
+            min_on_s: 300,
+            min_off_s: 120,
+        }
+    }
+}
+
+impl VentilationConfig {
+    /// The pin must be one of the `pins` outputs and `off_ppm` must not exceed `on_ppm`
+    pub fn is_valid(&self, pins: usize) -> bool {
+        self.pin.is_none_or(|pin| (pin as usize) < pins) && self.off_ppm <= self.on_ppm
+    }
+}
+
+pub struct Ventilation {
+    config: VentilationConfig,
+    on: bool,
+    // 上次切换的时间 (s)，启动或重新配置后为 None，可以立即切换
+    switched_s: Option<u32>,
+}
+
+impl Ventilation {
+    pub const fn new(config: VentilationConfig) -> Self {
+        Self {
+            config,
+            on: false,
+            switched_s: None,
+        }
+    }
+
+    pub const fn config(&self) -> VentilationConfig {
+        self.config
+    }
+
+    /// Replace the configuration, the output is switched off until the next reading
+    pub fn configure(&mut self, config: VentilationConfig) {
+        *self = Self::new(config);
+    }
+
+    pub const fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Feed a CO2 reading, returns whether the output should be on.
+    ///
+    /// Turns on at or above `on_ppm` and only turns off again at or below
+    /// `off_ppm`, but not before the output has been in its current state
+    /// for the configured minimum time.
+    pub fn update(&mut self, co2_ppm: u16, now_s: u32) -> bool {
+        if self.config.pin.is_none() {
+            self.on = false;
+            return false;
+        }
+        let wanted = if co2_ppm >= self.config.on_ppm {
+            true
+        } else if co2_ppm <= self.config.off_ppm.min(self.config.on_ppm) {
+            false
+        } else {
+            self.on
+        };
+        let min_s = if self.on {
+            self.config.min_on_s
+        } else {
+            self.config.min_off_s
+        };
+        let held = self
+            .switched_s
+            .is_none_or(|switched| now_s.wrapping_sub(switched) >= min_s as u32);
+        if wanted != self.on && held {
+            self.on = wanted;
+            self.switched_s = Some(now_s);
+        }
+        self.on
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIN: VentilationConfig = VentilationConfig {
+        pin: Some(0),
+        on_ppm: 1000,
+        off_ppm: 800,
+        min_on_s: 0,
+        min_off_s: 0,
+    };
+
+    #[test]
+    fn hysteresis() {
+        let mut ventilation = Ventilation::new(PIN);
+        let readings = [700, 999, 1000, 900, 801, 800, 900, 999, 1200, 500];
+        let expected = [
+            false, false, true, true, true, false, false, false, true, false,
+        ];
+        for (i, (co2, on)) in readings.into_iter().zip(expected).enumerate() {
+            assert_eq!(ventilation.update(co2, i as u32), on, "{co2} ppm");
+        }
+    }
+
+    #[test]
+    fn disabled_without_pin() {
+        let mut ventilation = Ventilation::new(VentilationConfig::default());
+        assert!(!ventilation.update(5000, 0));
+        assert!(!ventilation.is_on());
+    }
+
+    #[test]
+    fn minimum_on_time() {
+        let mut ventilation = Ventilation::new(VentilationConfig {
+            min_on_s: 300,
+            ..PIN
+        });
+        // 首次开启不受最短时间限制
+        assert!(ventilation.update(1100, 1000));
+        assert!(ventilation.update(600, 1100));
+        assert!(ventilation.update(600, 1299));
+        assert!(!ventilation.update(600, 1300));
+    }
+
+    #[test]
+    fn minimum_off_time() {
+        let mut ventilation = Ventilation::new(VentilationConfig {
+            min_off_s: 120,
+            ..PIN
+        });
+        assert!(ventilation.update(1100, 0));
+        assert!(!ventilation.update(700, 10));
+        assert!(!ventilation.update(1500, 50));
+        assert!(!ventilation.update(1500, 129));
+        assert!(ventilation.update(1500, 130));
+    }
+
+    #[test]
+    fn pending_switch_is_dropped_when_reading_recovers() {
+        // 最短开启时间内浓度回落又回升，不会关闭
+        let mut ventilation = Ventilation::new(VentilationConfig {
+            min_on_s: 60,
+            ..PIN
+        });
+        assert!(ventilation.update(1100, 0));
+        assert!(ventilation.update(700, 30));
+        assert!(ventilation.update(900, 90));
+        assert!(!ventilation.update(700, 95));
+    }
+
+    #[test]
+    fn configure_switches_off_and_resets_timers() {
+        let mut ventilation = Ventilation::new(VentilationConfig {
+            min_off_s: 600,
+            ..PIN
+        });
+        assert!(ventilation.update(1100, 0));
+        assert!(!ventilation.update(700, 1));
+        ventilation.configure(VentilationConfig {
+            min_off_s: 600,
+            ..PIN
+        });
+        assert!(!ventilation.is_on());
+        assert!(ventilation.update(1100, 2));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(VentilationConfig::default().is_valid(4));
+        assert!(PIN.is_valid(4));
+        assert!(
+            VentilationConfig {
+                pin: Some(3),
+                ..PIN
+            }
+            .is_valid(4)
+        );
+        assert!(
+            !VentilationConfig {
+                pin: Some(4),
+                ..PIN
+            }
+            .is_valid(4)
+        );
+        assert!(
+            !VentilationConfig {
+                pin: Some(0xFE),
+                ..PIN
+            }
+            .is_valid(4)
+        );
+        assert!(
+            !VentilationConfig {
+                off_ppm: 1001,
+                ..PIN
+            }
+            .is_valid(4)
+        );
+    }
+}
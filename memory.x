@@ -1,6 +1,7 @@
 MEMORY
 {
   /* NOTE K = KiBi = 1024 bytes */
-  FLASH : ORIGIN = 0x00000000, LENGTH = 512K
+  /* 最后 8K 留给 storage.rs 保存配置 */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 504K
   RAM : ORIGIN = 0x20000000, LENGTH = 128K
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use embassy_futures::select::{Either, select};
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    signal::Signal,
+    watch::{DynReceiver, Watch},
+};
+
+use crate::sense;
+
+pub use microbit_co2_core::air_quality::{
+    AirQuality, AirQualityConfig, AirQualityStatus, Classifier,
+};
+
+static CONFIG: Signal<ThreadModeRawMutex, AirQualityConfig> = Signal::new();
+
+// 空气质量消费者数量，分别是 display、ble 通知、广播和 sound
+const STATUS_CONSUMERS: usize = 4;
+static STATUS: Watch<ThreadModeRawMutex, AirQualityStatus, STATUS_CONSUMERS> = Watch::new();
+
+pub fn configure(config: AirQualityConfig) {
+    CONFIG.signal(config);
+}
+
+pub fn get_status_receiver() -> Option<DynReceiver<'static, AirQualityStatus>> {
+    STATUS.dyn_receiver()
+}
+
+#[embassy_executor::task]
+pub async fn air_quality_task() {
+    let mut rx_co2 = sense::get_co2_receiver().unwrap();
+    let tx = STATUS.sender();
+    let mut classifier = Classifier::new(AirQualityConfig::default());
+    loop {
+        let co2 = match select(rx_co2.changed(), CONFIG.wait()).await {
+            Either::First(co2) => co2,
+            Either::Second(config) => {
+                defmt::info!("[air] config: {:?}", config);
+                classifier.configure(config);
+                sense::set_ventilation_threshold(config.poor_ppm);
+                match rx_co2.try_get() {
+                    Some(co2) => co2,
+                    None => continue,
+                }
+            }
+        };
+        let status = classifier.update(co2);
+        tx.send_if_modified(|current| {
+            let modified = *current != Some(status);
+            *current = Some(status);
+            modified
+        });
+    }
+}
@@ -1,24 +1,43 @@
 #![allow(unused)]
 
+pub mod address;
 pub mod services;
 
+use core::pin::pin;
+
+use bt_hci::{cmd::le::LeSetRandomAddr, controller::ControllerCmdSync};
 use defmt::{info, warn};
 use embassy_executor::{SpawnToken, Spawner};
-use embassy_futures::select::select;
+use embassy_futures::{
+    join::{join, join4},
+    select::{Either, Either3, Either4, select, select3, select4},
+};
 use microbit_bsp::ble::{MultiprotocolServiceLayer, SoftdeviceController};
+use microbit_co2_core::advertising::AdvStatus;
 use static_cell::StaticCell;
 use trouble_host::prelude::*;
 
 use crate::{
+    air_quality,
     ble::services::{
         battery::BatteryService,
-        configuration::{BLE_NAME, ThingyConfigurationService},
+        configuration::ThingyConfigurationService,
         environment::{TesGas, TesTemperature, ThingyEnvironmentService},
-        motion::{ThingyMotionService, TmsGravity},
-        sound::ThingySoundService,
+        monitor::{
+            CmsAirQualityConfig, CmsAlarm, CmsClimate, CmsCo2Trend, CmsCompassCalibration,
+            CmsOccupancyConfig, Co2MonitorService, VENTILATION_NOT_DUE,
+        },
+        motion::{
+            ThingyMotionService, TmsEuler, TmsGravity, TmsPedometer, TmsQuaternion, TmsRaw,
+            TmsRotationMatrix,
+        },
+        sound::{ThingySoundService, TssMicrophone},
         ui::ThingyUiService,
     },
-    sense,
+    button, clock,
+    display::{self, DisplayMode, Led, led::ParseError},
+    microphone, motion, occupancy, pins, sense, sound,
+    storage::{self, Key},
 };
 
 #[gatt_server]
@@ -29,6 +48,7 @@ struct Server {
     sound: ThingySoundService,
     motion: ThingyMotionService,
     battery: BatteryService,
+    monitor: Co2MonitorService,
 }
 
 #[embassy_executor::task]
@@ -48,12 +68,12 @@ async fn host_task(mut runner: Runner<'static, SoftdeviceController<'static>, De
 pub async fn run(
     sdc: SoftdeviceController<'static>,
     mpsl: &'static MultiprotocolServiceLayer<'static>,
+    address: Address,
     spawner: Spawner,
 ) {
     spawner.must_spawn(mpsl_task(mpsl));
 
-    // static random address: client will remember...
-    let address: Address = Address::random([0xff, 0x8f, 0x1a, 0x05, 0xe4, 0xff]);
+    info!("[adv] address: {:?}", address);
     let resources = {
         static RESOURCES: StaticCell<BleHostResources> = StaticCell::new();
         RESOURCES.init(BleHostResources::new())
@@ -70,22 +90,69 @@ pub async fn run(
     } = stack.build();
     spawner.must_spawn(host_task(runner));
 
+    let name = address::device_name();
     let server = Server::new_with_config(GapConfig::Peripheral(PeripheralConfig {
-        name: BLE_NAME,
+        name,
         appearance: &appearance::power_device::GENERIC_POWER_DEVICE,
     }))
     .expect("Failed to create GATT server");
+    if let Ok(device_name) = name.parse() {
+        server.set(&server.config.device_name, &device_name).ok();
+    }
 
+    storage::init(mpsl).await;
+    spawner.must_spawn(storage::storage_task());
+    spawner.must_spawn(sense::die_temperature_task(mpsl));
+    load_settings(&server).await;
+
+    let mut rotation = address::Rotation::start();
     loop {
-        match advertise(&mut peripheral, &server).await {
+        match advertise(name, stack, &mut peripheral, &server, &mut rotation).await {
             Ok(conn) => {
-                select(gatt_events(&conn), env_notifier(&conn, &server)).await;
+                select(
+                    gatt_events(&conn, &server),
+                    join(
+                        join4(
+                            env_notifier(&conn, &server),
+                            ui_notifier(&conn, &server),
+                            sound_notifier(&conn, &server),
+                            monitor_notifier(&conn, &server),
+                        ),
+                        motion_notifier(&conn, &server),
+                    ),
+                )
+                .await;
+                // 断开后交还显示控制权，停止音频流
+                display::set_led(Led::Off);
+                microphone::set_streaming(false);
             }
             Err(e) => warn!("[adv] {:?}", e),
         }
     }
 }
 
+// 从 flash 恢复通过 BLE 写入的配置
+async fn load_settings(server: &Server<'_>) {
+    if let Some(config) = storage::load::<CmsAirQualityConfig>(Key::AirQuality).await {
+        server.set(&server.monitor.air_quality_config, &config).ok();
+        air_quality::configure(config.into());
+    }
+    if let Some(alarm) = storage::load::<CmsAlarm>(Key::Alarm).await {
+        server.set(&server.monitor.alarm, &alarm).ok();
+        sound::configure_alarm(alarm.into());
+    }
+    if let Some(calibration) = storage::load::<CmsCompassCalibration>(Key::Compass).await {
+        server
+            .set(&server.monitor.compass_calibration, &calibration)
+            .ok();
+        motion::set_compass_calibration(calibration.into());
+    }
+    if let Some(config) = storage::load::<CmsOccupancyConfig>(Key::Occupancy).await {
+        server.set(&server.monitor.occupancy_config, &config).ok();
+        occupancy::configure(config.into());
+    }
+}
+
 async fn env_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
     let gas = &server.env.gas;
     let mut rx_co2 = sense::get_co2_receiver().unwrap();
@@ -97,45 +164,366 @@ async fn env_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server:
     let mut rx_humidity = sense::get_humidity_receiver().unwrap();
 
     loop {
-        let co2 = rx_co2.changed().await;
-        if let Err(e) = gas.notify(conn, &TesGas::new(co2)).await {
+        let result = match select3(
+            rx_co2.changed(),
+            rx_temperature.changed(),
+            rx_humidity.changed(),
+        )
+        .await
+        {
+            Either3::First(co2) => gas.notify(conn, &TesGas::new(co2)).await,
+            Either3::Second(integer) => {
+                temperature
+                    .notify(conn, &TesTemperature::new(integer))
+                    .await
+            }
+            Either3::Third(value) => humidity.notify(conn, &value).await,
+        };
+        if let Err(e) = result {
             warn!("[gatt] notification error: {}", e);
         }
+    }
+}
 
-        let integer = rx_temperature.changed().await;
-        if let Err(e) = temperature
-            .notify(conn, &TesTemperature::new(integer))
-            .await
-        {
+async fn ui_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
+    let button = &server.ui.button;
+    let mut rx_button = button::get_state_receiver().unwrap();
+
+    let pin = &server.ui.pin;
+    let mut rx_pin = pins::get_state_receiver().unwrap();
+
+    loop {
+        match select(rx_button.changed(), rx_pin.changed()).await {
+            Either::First(state) => {
+                if let Err(e) = button.notify(conn, &state).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            // TUS_PIN 没有通知，只更新读取值
+            Either::Second(states) => {
+                server.set(pin, &u32::from_le_bytes(states)).ok();
+            }
+        }
+    }
+}
+
+async fn sound_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
+    let speaker_status = &server.sound.speaker_status;
+    let mut rx_status = sound::get_status_receiver().unwrap();
+
+    let microphone = &server.sound.microphone;
+    let rx_frames = microphone::frames();
+
+    let sound_level = &server.monitor.sound_level;
+    let mut rx_level = microphone::get_level_receiver().unwrap();
+
+    loop {
+        let result =
+            match select3(rx_status.changed(), rx_frames.receive(), rx_level.changed()).await {
+                Either3::First(status) => speaker_status.notify(conn, &(status as u8)).await,
+                Either3::Second(frame) => microphone.notify(conn, &TssMicrophone::new(frame)).await,
+                Either3::Third(level) => sound_level.notify(conn, &level).await,
+            };
+        if let Err(e) = result {
             warn!("[gatt] notification error: {}", e);
         }
+    }
+}
+
+async fn motion_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
+    let raw = &server.motion.raw;
+    let gravity = &server.motion.gravity;
+    let mut rx_reading = motion::get_reading_receiver().unwrap();
+
+    let quaternion = &server.motion.quaternion;
+    let euler = &server.motion.euler;
+    let rotation_matrix = &server.motion.rotation_matrix;
+    let mut rx_quaternion = motion::get_quaternion_receiver().unwrap();
 
-        let humidity_value = rx_humidity.changed().await;
-        if let Err(e) = humidity.notify(conn, &humidity_value).await {
+    let orientation = &server.motion.orientation;
+    let mut rx_orientation = motion::get_orientation_receiver().unwrap();
+
+    let heading = &server.motion.heading;
+    let mut rx_heading = motion::get_heading_receiver().unwrap();
+
+    let tap = &server.motion.tap;
+    let mut rx_tap = motion::get_tap_receiver().unwrap();
+
+    let pedometer = &server.motion.pedometer;
+    let mut rx_steps = motion::get_steps_receiver().unwrap();
+
+    loop {
+        let result = match select4(
+            rx_reading.changed(),
+            rx_quaternion.changed(),
+            select(rx_orientation.changed(), rx_heading.changed()),
+            select(rx_tap.changed(), rx_steps.changed()),
+        )
+        .await
+        {
+            Either4::First(reading) => match raw.notify(conn, &TmsRaw::from(reading)).await {
+                Ok(()) => gravity.notify(conn, &TmsGravity::from(reading.accel)).await,
+                Err(e) => Err(e),
+            },
+            Either4::Second(value) => {
+                async {
+                    quaternion.notify(conn, &TmsQuaternion::from(value)).await?;
+                    euler.notify(conn, &TmsEuler::from(value)).await?;
+                    rotation_matrix
+                        .notify(conn, &TmsRotationMatrix::from(value))
+                        .await
+                }
+                .await
+            }
+            Either4::Third(Either::First(value)) => orientation.notify(conn, &(value as u8)).await,
+            Either4::Third(Either::Second(value)) => heading.notify(conn, &value).await,
+            Either4::Fourth(Either::First(event)) => tap.notify(conn, &event.to_gatt()).await,
+            Either4::Fourth(Either::Second(steps)) => {
+                pedometer.notify(conn, &TmsPedometer::from(steps)).await
+            }
+        };
+        if let Err(e) = result {
             warn!("[gatt] notification error: {}", e);
         }
     }
 }
 
-async fn gatt_events(conn: &GattConnection<'_, '_, DefaultPacketPool>) {
+async fn monitor_notifier(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
+    let display_mode = &server.monitor.display_mode;
+    let mut rx_mode = display::get_mode_receiver().unwrap();
+
+    let air_quality = &server.monitor.air_quality;
+    let mut rx_air_quality = air_quality::get_status_receiver().unwrap();
+
+    let occupancy = &server.monitor.occupancy;
+    let mut rx_occupancy = occupancy::get_occupancy_receiver().unwrap();
+
+    let compass_calibration = &server.monitor.compass_calibration;
+    let mut rx_calibration = motion::get_calibration_receiver().unwrap();
+
+    let climate = &server.monitor.climate;
+    let mut rx_climate = sense::get_climate_receiver().unwrap();
+
+    let die_temperature = &server.monitor.die_temperature;
+    let mut rx_die = sense::get_die_temperature_receiver().unwrap();
+
+    let sensor_health = &server.monitor.sensor_health;
+    let mut rx_health = sense::get_health_receiver().unwrap();
+
+    let co2_trend = &server.monitor.co2_trend;
+    let mut rx_averages = sense::get_averages_receiver().unwrap();
+
+    let ventilation_due = &server.monitor.ventilation_due;
+    let mut rx_ventilation = sense::get_ventilation_receiver().unwrap();
+
+    loop {
+        match select4(
+            rx_mode.changed(),
+            select(rx_air_quality.changed(), rx_occupancy.changed()),
+            select3(
+                rx_calibration.changed(),
+                rx_climate.changed(),
+                rx_die.changed(),
+            ),
+            select3(
+                rx_health.changed(),
+                rx_averages.changed(),
+                rx_ventilation.changed(),
+            ),
+        )
+        .await
+        {
+            Either4::First(mode) => {
+                server.set(display_mode, &mode.into()).ok();
+            }
+            Either4::Second(Either::First(status)) => {
+                if let Err(e) = air_quality.notify(conn, &status.flags()).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Second(Either::Second(people)) => {
+                if let Err(e) = occupancy.notify(conn, &people).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Third(Either3::First(calibration)) => {
+                let value = CmsCompassCalibration::from(calibration);
+                if let Err(e) = compass_calibration.notify(conn, &value).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Third(Either3::Second(reading)) => {
+                let value = CmsClimate::from(reading);
+                if let Err(e) = climate.notify(conn, &value).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Third(Either3::Third(die_c)) => {
+                let value = (die_c * 100.0) as i16;
+                if let Err(e) = die_temperature.notify(conn, &value).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Fourth(Either3::First(health)) => {
+                if let Err(e) = sensor_health.notify(conn, &health).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Fourth(Either3::Second(averages)) => {
+                let value = CmsCo2Trend::from(averages);
+                if let Err(e) = co2_trend.notify(conn, &value).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+            Either4::Fourth(Either3::Third(minutes)) => {
+                let value = minutes.unwrap_or(VENTILATION_NOT_DUE);
+                if let Err(e) = ventilation_due.notify(conn, &value).await {
+                    warn!("[gatt] notification error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+async fn gatt_events(conn: &GattConnection<'_, '_, DefaultPacketPool>, server: &Server<'_>) {
     loop {
         match conn.next().await {
             GattConnectionEvent::Disconnected { reason } => {
                 info!("[gatt] disconnected: {:?}", reason);
                 break;
             }
-            GattConnectionEvent::Gatt { event } => match event.accept() {
-                Ok(reply) => reply.send().await,
-                Err(e) => warn!("[gatt] error processing request: {:?}", e),
-            },
+            GattConnectionEvent::Gatt { event } => {
+                let result = match &event {
+                    GattEvent::Write(write) => on_write(server, write),
+                    _ => Ok(()),
+                };
+                let reply = match result {
+                    Ok(()) => event.accept(),
+                    Err(code) => event.reject(code),
+                };
+                match reply {
+                    Ok(reply) => reply.send().await,
+                    Err(e) => warn!("[gatt] error processing request: {:?}", e),
+                }
+            }
             _ => (),
         }
     }
 }
 
-async fn advertise<'a, 'b, C: Controller>(
+// 返回错误时拒绝写入，特征值保持不变
+fn on_write(
+    server: &Server<'_>,
+    write: &WriteEvent<'_, '_, DefaultPacketPool>,
+) -> Result<(), AttErrorCode> {
+    let handle = write.handle();
+    if handle == server.ui.led.handle {
+        let led = Led::parse(write.data()).map_err(|e| {
+            warn!("[gatt] invalid led data {:?}: {:?}", write.data(), e);
+            match e {
+                ParseError::Length => AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH,
+                ParseError::Value => AttErrorCode::VALUE_NOT_ALLOWED,
+            }
+        })?;
+        info!("[gatt] led: {:?}", led);
+        display::set_led(led);
+    } else if handle == server.ui.pin.handle {
+        let states = write
+            .value(&server.ui.pin)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        pins::write(states.to_le_bytes());
+    } else if handle == server.sound.configuration.handle {
+        let value = write
+            .value(&server.sound.configuration)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        let mode = sound::SpeakerMode::try_from(value.speaker_mode)
+            .map_err(|_| AttErrorCode::VALUE_NOT_ALLOWED)?;
+        sound::set_speaker_mode(mode);
+        // 麦克风模式 1 为 ADPCM 音频流
+        microphone::set_streaming(value.microphone_mode == 0x01);
+    } else if handle == server.sound.speaker.handle {
+        sound::speaker_write(write.data());
+    } else if handle == server.motion.config.handle {
+        let value = write
+            .value(&server.motion.config)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        let config = motion::MotionConfig::try_from(value).map_err(|_| {
+            warn!("[gatt] motion temperature interval isn't supported");
+            AttErrorCode::VALUE_NOT_ALLOWED
+        })?;
+        if !config.step.is_valid() {
+            warn!("[gatt] invalid step config: {:?}", config.step);
+            return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+        }
+        motion::configure(config);
+    } else if handle == server.monitor.ventilation.handle {
+        let value = write
+            .value(&server.monitor.ventilation)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        pins::configure_ventilation(value.into()).map_err(|_| AttErrorCode::VALUE_NOT_ALLOWED)?;
+    } else if handle == server.monitor.display_mode.handle {
+        let value = write
+            .value(&server.monitor.display_mode)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        let mode = DisplayMode::try_from(value).map_err(|_| {
+            warn!("[gatt] invalid display mode: {}", value);
+            AttErrorCode::VALUE_NOT_ALLOWED
+        })?;
+        display::send(display::Command::SetMode(mode));
+    } else if handle == server.monitor.air_quality_config.handle {
+        let value = write
+            .value(&server.monitor.air_quality_config)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        let config: air_quality::AirQualityConfig = value.into();
+        if !config.is_valid() {
+            warn!("[gatt] invalid air quality config: {:?}", config);
+            return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+        }
+        air_quality::configure(config);
+        storage::save(Key::AirQuality, &value);
+    } else if handle == server.monitor.alarm.handle {
+        let value = write
+            .value(&server.monitor.alarm)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        sound::configure_alarm(value.into());
+        storage::save(Key::Alarm, &value);
+    } else if handle == server.monitor.compass_calibrate.handle {
+        match write.value(&server.monitor.compass_calibrate) {
+            Ok(0) => motion::clear_compass_calibration(),
+            Ok(1) => motion::calibrate_compass(),
+            _ => return Err(AttErrorCode::VALUE_NOT_ALLOWED),
+        }
+    } else if handle == server.monitor.compass_calibration.handle {
+        let value = write
+            .value(&server.monitor.compass_calibration)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        motion::set_compass_calibration(value.into());
+        storage::save(Key::Compass, &value);
+    } else if handle == server.monitor.occupancy_config.handle {
+        let value = write
+            .value(&server.monitor.occupancy_config)
+            .map_err(|_| AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH)?;
+        let config: occupancy::OccupancyConfig = value.into();
+        if !config.is_valid() {
+            warn!("[gatt] invalid occupancy config: {:?}", config);
+            return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+        }
+        occupancy::configure(config);
+        storage::save(Key::Occupancy, &value);
+    } else if handle == server.monitor.time.handle {
+        if let Ok(seconds) = write.value(&server.monitor.time) {
+            clock::set_time_of_day(seconds);
+        }
+    }
+    Ok(())
+}
+
+async fn advertise<'a, 'b, C: Controller + ControllerCmdSync<LeSetRandomAddr>>(
+    name: &str,
+    stack: &Stack<'_, C, DefaultPacketPool>,
     peripheral: &mut Peripheral<'a, C, DefaultPacketPool>,
     server: &'b Server<'_>,
+    rotation: &mut address::Rotation,
 ) -> Result<GattConnection<'a, 'b, DefaultPacketPool>, BleHostError<C::Error>> {
     const GAP_ADV_LIMIT: usize = 31;
     let mut ad_data = [0u8; GAP_ADV_LIMIT];
@@ -143,29 +531,61 @@ async fn advertise<'a, 'b, C: Controller>(
         &[
             AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
             AdStructure::ServiceUuids128(&[services::configuration::TCS.into()]),
-            AdStructure::CompleteLocalName(BLE_NAME.as_bytes()),
         ],
         &mut ad_data[..],
     )?;
+    // 128 位 UUID 已经占满广播包，带后缀的名称放到扫描响应中
     let mut sr_data = [0u8; GAP_ADV_LIMIT];
-    let sr_len = AdStructure::encode_slice(
-        &[AdStructure::ManufacturerSpecificData {
-            company_identifier: services::configuration::MSP_NORDIC_COMPANY_ID,
-            payload: &services::configuration::MSP_PAYLOAD,
-        }],
-        &mut sr_data[..],
-    )?;
-    let advertiser = peripheral
-        .advertise(
-            &Default::default(),
-            Advertisement::ConnectableScannableUndirected {
-                adv_data: &ad_data[0..ad_len],
-                scan_data: &sr_data[0..sr_len],
-            },
-        )
-        .await?;
-    info!("[adv] Advertising; waiting for connection...");
-    let conn = advertiser.accept().await?.with_attribute_server(server)?;
-    info!("[adv] Connection established");
-    Ok(conn)
+    let mut rx_air_quality = air_quality::get_status_receiver().unwrap();
+    let mut rx_ventilation = sense::get_ventilation_receiver().unwrap();
+    let mut status = AdvStatus {
+        air_quality: rx_air_quality.try_get(),
+        ventilation_due: rx_ventilation.try_get().flatten(),
+    };
+    loop {
+        // 停止广播时才能更换地址
+        rotation.rotate(stack).await?;
+        let payload = status.payload();
+        let sr_len = AdStructure::encode_slice(
+            &[
+                AdStructure::CompleteLocalName(name.as_bytes()),
+                AdStructure::ManufacturerSpecificData {
+                    company_identifier: services::configuration::MSP_NORDIC_COMPANY_ID,
+                    payload: &payload,
+                },
+            ],
+            &mut sr_data[..],
+        )?;
+        let advertiser = peripheral
+            .advertise(
+                &Default::default(),
+                Advertisement::ConnectableScannableUndirected {
+                    adv_data: &ad_data[0..ad_len],
+                    scan_data: &sr_data[0..sr_len],
+                },
+            )
+            .await?;
+        info!("[adv] Advertising; waiting for connection...");
+        let mut accept = pin!(advertiser.accept());
+        // 载荷变化或地址到期时才重新开始广播
+        while status.payload() == payload {
+            match select4(
+                accept.as_mut(),
+                rx_air_quality.changed(),
+                rx_ventilation.changed(),
+                rotation.due(),
+            )
+            .await
+            {
+                Either4::First(conn) => {
+                    let conn = conn?.with_attribute_server(server)?;
+                    info!("[adv] Connection established");
+                    return Ok(conn);
+                }
+                Either4::Second(air_quality) => status.air_quality = Some(air_quality),
+                Either4::Third(minutes) => status.ventilation_due = minutes,
+                Either4::Fourth(_) => break,
+            }
+        }
+    }
 }
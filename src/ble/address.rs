@@ -0,0 +1,151 @@
+use core::fmt::Write;
+
+use bt_hci::{cmd::le::LeSetRandomAddr, controller::ControllerCmdSync};
+use embassy_time::{Duration, Instant, Timer};
+use heapless::String;
+use microbit_bsp::embassy_nrf::pac::FICR;
+use static_cell::StaticCell;
+use trouble_host::prelude::*;
+
+use super::services::configuration::BLE_NAME;
+
+// "microbit-" + 4 位十六进制后缀
+pub const DEVICE_NAME_LEN: usize = 16;
+
+/// 64-bit unique device ID programmed into FICR at the factory
+pub fn device_id() -> u64 {
+    let low = FICR.deviceid(0).read();
+    let high = FICR.deviceid(1).read();
+    ((high as u64) << 32) | low as u64
+}
+
+/// Static random address derived from the device ID.
+///
+/// Stable across resets and re-flashing, unique per chip. The two most
+/// significant bits are set as required by Core Spec Vol 6 Part B 1.3.2.1.
+pub fn static_random_address() -> Address {
+    let id = device_id().to_le_bytes();
+    let mut addr = [id[0], id[1], id[2], id[3], id[4], id[5]];
+    addr[5] |= 0b1100_0000;
+    Address::random(addr)
+}
+
+/// Advertised name with the low 16 bits of the device ID as suffix, e.g. `microbit-1A2B`.
+///
+/// Kept with the `rpa` feature too: without bonding no central learns the
+/// IRK, so the suffix is what tells identical units apart.
+pub fn device_name() -> &'static str {
+    static NAME: StaticCell<String<DEVICE_NAME_LEN>> = StaticCell::new();
+    let name = NAME.init(String::new());
+    write!(name, "{}-{:04X}", BLE_NAME, device_id() as u16).ok();
+    name.as_str()
+}
+
+/// Address handed to the host stack: a fresh RPA with the `rpa` feature, otherwise the static one.
+///
+/// Call before `ble.init()`, the RPA draws its `prand` from the RNG peripheral.
+pub fn local_address() -> Address {
+    #[cfg(feature = "rpa")]
+    {
+        resolvable_private_address()
+    }
+    #[cfg(not(feature = "rpa"))]
+    {
+        static_random_address()
+    }
+}
+
+/// How long an RPA is used, the 15 minutes recommended by Core Spec Vol 3 Part C Appendix A
+pub const RPA_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Generates a new RPA every `RPA_TIMEOUT`, a no-op with the static address.
+pub struct Rotation {
+    next: Instant,
+}
+
+impl Rotation {
+    /// Starts timing the address set with `local_address`
+    pub fn start() -> Self {
+        Self {
+            next: Instant::now() + RPA_TIMEOUT,
+        }
+    }
+
+    /// Resolves when the current address is due to be replaced
+    pub async fn due(&self) {
+        if cfg!(feature = "rpa") {
+            Timer::at(self.next).await
+        } else {
+            core::future::pending().await
+        }
+    }
+
+    /// Switch to a new address if due, the controller only accepts it while not advertising
+    pub async fn rotate<C: Controller + ControllerCmdSync<LeSetRandomAddr>>(
+        &mut self,
+        stack: &Stack<'_, C, DefaultPacketPool>,
+    ) -> Result<(), BleHostError<C::Error>> {
+        #[cfg(feature = "rpa")]
+        if Instant::now() >= self.next {
+            let address = resolvable_private_address();
+            stack.command(LeSetRandomAddr::new(address.addr)).await?;
+            self.next = Instant::now() + RPA_TIMEOUT;
+            defmt::info!("[adv] address: {:?}", address);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rpa")]
+pub use rpa::resolvable_private_address;
+
+#[cfg(feature = "rpa")]
+mod rpa {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use embassy_sync::once_lock::OnceLock;
+    use microbit_bsp::embassy_nrf::pac::{FICR, RNG};
+    use microbit_co2_core::rpa;
+    use trouble_host::prelude::*;
+
+    // 启动时从 RNG 取得的密钥，RNG 交给 SDC 后用它加密计数器生成 prand
+    static PRAND_KEY: OnceLock<[u8; 16]> = OnceLock::new();
+    static PRAND_COUNTER: AtomicU32 = AtomicU32::new(0);
+    static IRK: OnceLock<[u8; 16]> = OnceLock::new();
+
+    /// Resolvable private address, Core Spec Vol 6 Part B 1.3.2.2.
+    ///
+    /// The first call must happen before the RNG is handed over to the
+    /// softdevice controller, it seeds the generator used by later calls.
+    pub fn resolvable_private_address() -> Address {
+        let key = PRAND_KEY.get_or_init(random_key);
+        let mut block = [0u8; 16];
+        block[..4].copy_from_slice(&PRAND_COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+        let block = rpa::encrypt(key, block);
+        let random = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        let irk = IRK.get_or_init(|| rpa::identity_resolving_key(&identity_root()));
+        Address::random(rpa::address(irk, random))
+    }
+
+    // FICR 中出厂写入的 IR，按大端排列
+    fn identity_root() -> [u8; 16] {
+        let mut ir = [0u8; 16];
+        for (i, chunk) in ir.chunks_exact_mut(4).enumerate() {
+            chunk.copy_from_slice(&FICR.ir(i).read().to_be_bytes());
+        }
+        ir
+    }
+
+    fn random_key() -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        RNG.config().write(|w| w.set_dercen(true));
+        RNG.tasks_start().write_value(1);
+        for byte in bytes.iter_mut() {
+            while RNG.events_valrdy().read() == 0 {}
+            RNG.events_valrdy().write_value(0);
+            *byte = RNG.value().read().value();
+        }
+        RNG.tasks_stop().write_value(1);
+        bytes
+    }
+}
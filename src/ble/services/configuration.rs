@@ -1,7 +1,8 @@
 use heapless::String;
+use microbit_co2_core::{impl_fixedgattvalue, units::ToTimeUnits};
 use trouble_host::prelude::*;
 
-use crate::impl_fixedgattvalue;
+use crate::ble::address::DEVICE_NAME_LEN;
 
 use super::ThingyUuid;
 
@@ -18,12 +19,11 @@ const TCS_NFC: ThingyUuid = ThingyUuid(0x0109);
 
 pub const BLE_NAME: &str = "microbit";
 pub const MSP_NORDIC_COMPANY_ID: u16 = 0x0059;
-pub const MSP_PAYLOAD: [u8; 4] = [0x01, 0x02, 0x03, 0x04];
 
 #[gatt_service(uuid = TCS)]
 pub struct ThingyConfigurationService {
     #[characteristic(uuid = TCS_DEVICE_NAME, read, write, value = BLE_NAME.parse().unwrap())]
-    device_name: String<10>,
+    pub device_name: String<DEVICE_NAME_LEN>,
     #[characteristic(uuid = TCS_ADV_PARAMS, read, write)]
     adv_params: TcsAdvertisingParameters,
     #[characteristic(uuid = TCS_CONN_PARAMS, read, write)]
@@ -68,18 +68,6 @@ impl From<AdvertisingParameters> for TcsAdvertisingParameters {
 
 impl_fixedgattvalue!(TcsAdvertisingParameters);
 
-trait ToTimeUnits {
-    const DIVISOR: usize;
-
-    fn from_ms(ms: usize) -> usize {
-        Self::from_us(ms * 1000)
-    }
-
-    fn from_us(us: usize) -> usize {
-        us / Self::DIVISOR
-    }
-}
-
 pub struct AdvertisingParameters {
     interval_ms: usize,
     timeout_s: usize,
@@ -1,7 +1,6 @@
+use microbit_co2_core::impl_fixedgattvalue;
 use trouble_host::prelude::*;
 
-use crate::impl_fixedgattvalue;
-
 use super::ThingyUuid;
 
 pub const TES: ThingyUuid = ThingyUuid(0x0200);
@@ -0,0 +1,361 @@
+use microbit_co2_core::{
+    climate::{Climate, Comfort},
+    impl_fixedgattvalue,
+    trend::{Averages, Trend},
+};
+use trouble_host::prelude::*;
+
+use crate::{
+    air_quality::AirQualityConfig, motion::Calibration, occupancy::OccupancyConfig,
+    pins::VentilationConfig, sound::AlarmConfig,
+};
+
+use super::VendorUuid;
+
+pub const CMS: VendorUuid = VendorUuid(0x0100);
+
+const CMS_VENTILATION: VendorUuid = VendorUuid(0x0101);
+const CMS_DISPLAY_MODE: VendorUuid = VendorUuid(0x0102);
+const CMS_AIR_QUALITY: VendorUuid = VendorUuid(0x0103);
+const CMS_AIR_QUALITY_CONFIG: VendorUuid = VendorUuid(0x0104);
+const CMS_ALARM: VendorUuid = VendorUuid(0x0105);
+const CMS_TIME: VendorUuid = VendorUuid(0x0106);
+const CMS_SOUND_LEVEL: VendorUuid = VendorUuid(0x0107);
+const CMS_COMPASS_CALIBRATE: VendorUuid = VendorUuid(0x0108);
+const CMS_COMPASS_CALIBRATION: VendorUuid = VendorUuid(0x0109);
+const CMS_SENSOR_HEALTH: VendorUuid = VendorUuid(0x010A);
+const CMS_CO2_TREND: VendorUuid = VendorUuid(0x010B);
+const CMS_VENTILATION_DUE: VendorUuid = VendorUuid(0x010C);
+const CMS_OCCUPANCY_CONFIG: VendorUuid = VendorUuid(0x010D);
+const CMS_OCCUPANCY: VendorUuid = VendorUuid(0x010E);
+const CMS_CLIMATE: VendorUuid = VendorUuid(0x010F);
+const CMS_DIE_TEMPERATURE: VendorUuid = VendorUuid(0x0110);
+
+// 引脚编号为该值时关闭通风自动控制
+const PIN_DISABLED: u8 = 0xFF;
+
+/// CO2 monitor specific settings and data that have no Thingy equivalent
+#[gatt_service(uuid = CMS)]
+pub struct Co2MonitorService {
+    #[characteristic(uuid = CMS_VENTILATION, read, write)]
+    pub ventilation: CmsVentilation,
+    /// 0 CO2, 1 temperature, 2 humidity, 3 carousel, 4 bar only, 5 off, 6 dew point
+    #[characteristic(uuid = CMS_DISPLAY_MODE, read, write, value = 0)]
+    pub display_mode: u8,
+    /// Bits 0-1: good/moderate/poor/unhealthy, bit 2: alarm
+    #[characteristic(uuid = CMS_AIR_QUALITY, read, notify, value = 0)]
+    pub air_quality: u8,
+    #[characteristic(uuid = CMS_AIR_QUALITY_CONFIG, read, write)]
+    pub air_quality_config: CmsAirQualityConfig,
+    #[characteristic(uuid = CMS_ALARM, read, write)]
+    pub alarm: CmsAlarm,
+    /// Local time as seconds since midnight, used for the alarm quiet hours
+    #[characteristic(uuid = CMS_TIME, write, value = 0)]
+    pub time: u32,
+    /// A-weighted-ish sound level in dB SPL
+    #[characteristic(uuid = CMS_SOUND_LEVEL, read, notify, value = 0)]
+    pub sound_level: u8,
+    /// 1 starts a 20 s calibration while the board is turned around, 0 clears it
+    #[characteristic(uuid = CMS_COMPASS_CALIBRATE, write, value = 0)]
+    pub compass_calibrate: u8,
+    #[characteristic(uuid = CMS_COMPASS_CALIBRATION, read, write, notify)]
+    pub compass_calibration: CmsCompassCalibration,
+    /// Bit 0: SCD4x temperature disagrees with the die, bit 1: temperature from the die,
+    /// bit 2: sensor warming up, bit 3: CO2 reading out of range, bit 4: no CO2 sensor found
+    #[characteristic(uuid = CMS_SENSOR_HEALTH, read, notify, value = 0)]
+    pub sensor_health: u8,
+    #[characteristic(uuid = CMS_CO2_TREND, read, notify)]
+    pub co2_trend: CmsCo2Trend,
+    /// Minutes until CO2 reaches the configured poor threshold at its current rate
+    /// of rise, 0 when it already has, 0xFFFF when it isn't rising towards it
+    #[characteristic(uuid = CMS_VENTILATION_DUE, read, notify, value = VENTILATION_NOT_DUE)]
+    pub ventilation_due: u16,
+    #[characteristic(uuid = CMS_OCCUPANCY_CONFIG, read, write)]
+    pub occupancy_config: CmsOccupancyConfig,
+    /// Approximate number of people, estimated from the CO2 mass balance
+    #[characteristic(uuid = CMS_OCCUPANCY, read, notify, value = 0)]
+    pub occupancy: u8,
+    #[characteristic(uuid = CMS_CLIMATE, read, notify)]
+    pub climate: CmsClimate,
+    /// nRF52 die temperature in 0.01 °C, before the self-heating offset is removed
+    #[characteristic(uuid = CMS_DIE_TEMPERATURE, read, notify, value = 0)]
+    pub die_temperature: i16,
+}
+
+/// `ventilation_due` when no ventilation is needed
+pub const VENTILATION_NOT_DUE: u16 = 0xFFFF;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct CmsVentilation {
+    pin: u8,
+    on_ppm: u16,
+    off_ppm: u16,
+    min_on_s: u16,
+    min_off_s: u16,
+}
+
+impl Default for CmsVentilation {
+    fn default() -> Self {
+        VentilationConfig::default().into()
+    }
+}
+
+impl From<VentilationConfig> for CmsVentilation {
+    fn from(value: VentilationConfig) -> Self {
+        Self {
+            pin: value.pin.unwrap_or(PIN_DISABLED),
+            on_ppm: value.on_ppm,
+            off_ppm: value.off_ppm,
+            min_on_s: value.min_on_s,
+            min_off_s: value.min_off_s,
+        }
+    }
+}
+
+impl From<CmsVentilation> for VentilationConfig {
+    fn from(value: CmsVentilation) -> Self {
+        Self {
+            pin: (value.pin != PIN_DISABLED).then_some(value.pin),
+            on_ppm: value.on_ppm,
+            off_ppm: value.off_ppm,
+            min_on_s: value.min_on_s,
+            min_off_s: value.min_off_s,
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsVentilation);
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct CmsAirQualityConfig {
+    moderate_ppm: u16,
+    poor_ppm: u16,
+    unhealthy_ppm: u16,
+    alarm_ppm: u16,
+    hysteresis_ppm: u16,
+}
+
+impl Default for CmsAirQualityConfig {
+    fn default() -> Self {
+        AirQualityConfig::default().into()
+    }
+}
+
+impl From<AirQualityConfig> for CmsAirQualityConfig {
+    fn from(value: AirQualityConfig) -> Self {
+        Self {
+            moderate_ppm: value.moderate_ppm,
+            poor_ppm: value.poor_ppm,
+            unhealthy_ppm: value.unhealthy_ppm,
+            alarm_ppm: value.alarm_ppm,
+            hysteresis_ppm: value.hysteresis_ppm,
+        }
+    }
+}
+
+impl From<CmsAirQualityConfig> for AirQualityConfig {
+    fn from(value: CmsAirQualityConfig) -> Self {
+        Self {
+            moderate_ppm: value.moderate_ppm,
+            poor_ppm: value.poor_ppm,
+            unhealthy_ppm: value.unhealthy_ppm,
+            alarm_ppm: value.alarm_ppm,
+            hysteresis_ppm: value.hysteresis_ppm,
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsAirQualityConfig);
+
+// CmsAlarm.flags
+const ALARM_MUTED: u8 = 0x01;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct CmsAlarm {
+    flags: u8,
+    frequency_hz: u16,
+    on_ms: u16,
+    off_ms: u16,
+    snooze_min: u8,
+    quiet_start_min: u16,
+    quiet_end_min: u16,
+}
+
+impl Default for CmsAlarm {
+    fn default() -> Self {
+        AlarmConfig::default().into()
+    }
+}
+
+impl From<AlarmConfig> for CmsAlarm {
+    fn from(value: AlarmConfig) -> Self {
+        Self {
+            flags: if value.muted { ALARM_MUTED } else { 0 },
+            frequency_hz: value.frequency_hz,
+            on_ms: value.on_ms,
+            off_ms: value.off_ms,
+            snooze_min: value.snooze_min,
+            quiet_start_min: value.quiet_start_min,
+            quiet_end_min: value.quiet_end_min,
+        }
+    }
+}
+
+impl From<CmsAlarm> for AlarmConfig {
+    fn from(value: CmsAlarm) -> Self {
+        Self {
+            muted: value.flags & ALARM_MUTED != 0,
+            frequency_hz: value.frequency_hz,
+            on_ms: value.on_ms,
+            off_ms: value.off_ms,
+            snooze_min: value.snooze_min,
+            quiet_start_min: value.quiet_start_min,
+            quiet_end_min: value.quiet_end_min,
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsAlarm);
+
+/// Magnetometer hard-iron offset in nT and soft-iron scale in Q4.12
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct CmsCompassCalibration {
+    offset_nt: [i32; 3],
+    scale: [u16; 3],
+}
+
+impl CmsCompassCalibration {
+    const SCALE_ONE: f32 = 4096.0;
+}
+
+impl Default for CmsCompassCalibration {
+    fn default() -> Self {
+        Calibration::default().into()
+    }
+}
+
+impl From<Calibration> for CmsCompassCalibration {
+    fn from(value: Calibration) -> Self {
+        Self {
+            offset_nt: value.offset.map(|v| v as i32),
+            scale: value.scale.map(|v| (v * Self::SCALE_ONE) as u16),
+        }
+    }
+}
+
+impl From<CmsCompassCalibration> for Calibration {
+    fn from(value: CmsCompassCalibration) -> Self {
+        let (offset_nt, scale) = (value.offset_nt, value.scale);
+        Self {
+            offset: offset_nt.map(|v| v as f32),
+            scale: scale.map(|v| v as f32 / CmsCompassCalibration::SCALE_ONE),
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsCompassCalibration);
+
+/// Room volume in m³, outdoor air supply in m³/h, CO2 exhaled per person in
+/// L/h (all f32) and outdoor CO2 in ppm
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct CmsOccupancyConfig {
+    volume_m3: f32,
+    ventilation_m3_per_h: f32,
+    generation_l_per_h: f32,
+    outdoor_ppm: u16,
+}
+
+impl Default for CmsOccupancyConfig {
+    fn default() -> Self {
+        OccupancyConfig::default().into()
+    }
+}
+
+impl From<OccupancyConfig> for CmsOccupancyConfig {
+    fn from(value: OccupancyConfig) -> Self {
+        Self {
+            volume_m3: value.volume_m3,
+            ventilation_m3_per_h: value.ventilation_m3_per_h,
+            generation_l_per_h: value.generation_l_per_h,
+            outdoor_ppm: value.outdoor_ppm,
+        }
+    }
+}
+
+impl From<CmsOccupancyConfig> for OccupancyConfig {
+    fn from(value: CmsOccupancyConfig) -> Self {
+        Self {
+            volume_m3: value.volume_m3,
+            ventilation_m3_per_h: value.ventilation_m3_per_h,
+            generation_l_per_h: value.generation_l_per_h,
+            outdoor_ppm: value.outdoor_ppm,
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsOccupancyConfig);
+
+/// Rolling CO2 averages in ppm, the change over the last five minutes in
+/// ppm/min and the trend: -1 falling, 0 stable, 1 rising
+#[repr(C, packed)]
+#[derive(Clone, Copy, Default)]
+pub struct CmsCo2Trend {
+    avg_1min: u16,
+    avg_5min: u16,
+    avg_1h: u16,
+    rate_ppm_per_min: i16,
+    trend: i8,
+}
+
+impl From<Averages> for CmsCo2Trend {
+    fn from(value: Averages) -> Self {
+        Self {
+            avg_1min: value.avg_1min,
+            avg_5min: value.avg_5min,
+            avg_1h: value.avg_1h,
+            rate_ppm_per_min: value.rate_ppm_per_min,
+            trend: match value.trend {
+                Trend::Falling => -1,
+                Trend::Stable => 0,
+                Trend::Rising => 1,
+            },
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsCo2Trend);
+
+/// Dew point and heat index in 0.01 °C, absolute humidity in 0.01 g/m³ and the
+/// comfort class: 0 cold, 1 dry, 2 comfortable, 3 humid, 4 hot
+#[repr(C, packed)]
+#[derive(Clone, Copy, Default)]
+pub struct CmsClimate {
+    dew_point: i16,
+    absolute_humidity: u16,
+    heat_index: i16,
+    comfort: u8,
+}
+
+impl From<Climate> for CmsClimate {
+    fn from(value: Climate) -> Self {
+        Self {
+            dew_point: (value.dew_point_c * 100.0) as i16,
+            absolute_humidity: (value.absolute_humidity_g_m3 * 100.0) as u16,
+            heat_index: (value.heat_index_c * 100.0) as i16,
+            comfort: match value.comfort {
+                Comfort::Cold => 0,
+                Comfort::Dry => 1,
+                Comfort::Comfortable => 2,
+                Comfort::Humid => 3,
+                Comfort::Hot => 4,
+            },
+        }
+    }
+}
+
+impl_fixedgattvalue!(CmsClimate);
@@ -1,7 +1,8 @@
 use microbit_bsp::lsm303agr::Acceleration;
+use microbit_co2_core::impl_fixedgattvalue;
 use trouble_host::{prelude::*, types::gatt_traits::FromGattError};
 
-use crate::impl_fixedgattvalue;
+use crate::motion::{MotionConfig, Quaternion, Reading, StepConfig, Steps};
 
 use super::ThingyUuid;
 
@@ -27,15 +28,15 @@ pub struct ThingyMotionService {
     #[characteristic(uuid = TMS_ORIENTATION, notify)]
     pub orientation: u8,
     #[characteristic(uuid = TMS_QUATERNION, notify)]
-    pub quaternion: [u8; 16],
+    pub quaternion: TmsQuaternion,
     #[characteristic(uuid = TMS_PEDOMETER, notify)]
-    pub pedometer: u64,
+    pub pedometer: TmsPedometer,
     #[characteristic(uuid = TMS_RAW, notify)]
-    pub raw: [u8; 18],
+    pub raw: TmsRaw,
     #[characteristic(uuid = TMS_EULER, notify)]
-    pub euler: [u8; 12],
+    pub euler: TmsEuler,
     #[characteristic(uuid = TMS_ROTATION_MATRIX, notify)]
-    pub rotation_matrix: [u8; 18],
+    pub rotation_matrix: TmsRotationMatrix,
     #[characteristic(uuid = TMS_HEADING, notify)]
     pub heading: i32,
     #[characteristic(uuid = TMS_GRAVITY, notify)]
@@ -50,22 +51,162 @@ pub struct TmsConfiguration {
     compass_interval_ms: u16,
     motion_frequency_hz: u16,
     wake_on_motion: u8,
+    // 以下为计步参数，排在 Thingy 原有字段之后
+    step_threshold_mg: u16,
+    step_hysteresis_mg: u16,
+    step_min_interval_ms: u16,
+    carry_steps: u8,
+    carry_window_ms: u16,
 }
 
+// 没有需要温度补偿的陀螺仪，温度间隔只接受该值
+const TEMPERATURE_INTERVAL_MS: u16 = 0;
+
 impl Default for TmsConfiguration {
     fn default() -> Self {
+        MotionConfig::default().into()
+    }
+}
+
+impl From<MotionConfig> for TmsConfiguration {
+    fn from(value: MotionConfig) -> Self {
         Self {
-            pedometer_interval_ms: 1000,
-            temperature_interval_ms: 500,
-            compass_interval_ms: 500,
-            motion_frequency_hz: 10,
-            wake_on_motion: 1,
+            pedometer_interval_ms: value.pedometer_interval_ms,
+            temperature_interval_ms: TEMPERATURE_INTERVAL_MS,
+            compass_interval_ms: value.compass_interval_ms,
+            motion_frequency_hz: value.motion_frequency_hz,
+            wake_on_motion: value.wake_on_motion as u8,
+            step_threshold_mg: value.step.threshold_mg,
+            step_hysteresis_mg: value.step.hysteresis_mg,
+            step_min_interval_ms: value.step.min_interval_ms,
+            carry_steps: value.step.carry_steps,
+            carry_window_ms: value.step.carry_window_ms,
+        }
+    }
+}
+
+impl TryFrom<TmsConfiguration> for MotionConfig {
+    type Error = ();
+
+    fn try_from(value: TmsConfiguration) -> Result<Self, Self::Error> {
+        if value.temperature_interval_ms != TEMPERATURE_INTERVAL_MS {
+            return Err(());
         }
+        Ok(Self {
+            pedometer_interval_ms: value.pedometer_interval_ms,
+            compass_interval_ms: value.compass_interval_ms,
+            motion_frequency_hz: value.motion_frequency_hz,
+            wake_on_motion: value.wake_on_motion != 0,
+            step: StepConfig {
+                threshold_mg: value.step_threshold_mg,
+                hysteresis_mg: value.step_hysteresis_mg,
+                min_interval_ms: value.step_min_interval_ms,
+                carry_steps: value.carry_steps,
+                carry_window_ms: value.carry_window_ms,
+            },
+        })
     }
 }
 
 impl_fixedgattvalue!(TmsConfiguration);
 
+/// Step count and the time in ms the count covers
+#[repr(C, packed)]
+#[derive(Default, Clone, Copy)]
+pub struct TmsPedometer {
+    steps: u32,
+    time_ms: u32,
+}
+
+impl From<Steps> for TmsPedometer {
+    fn from(value: Steps) -> Self {
+        Self {
+            steps: value.steps,
+            time_ms: value.elapsed_ms,
+        }
+    }
+}
+
+impl_fixedgattvalue!(TmsPedometer);
+
+/// Accelerometer in Q6.10 g, gyroscope in Q11.5 deg/s and compass in Q12.4 uT.
+/// There is no gyroscope on the micro:bit, it always reads zero.
+#[repr(C, packed)]
+#[derive(Default, Clone, Copy)]
+pub struct TmsRaw {
+    accel: [i16; 3],
+    gyro: [i16; 3],
+    compass: [i16; 3],
+}
+
+impl From<Reading> for TmsRaw {
+    fn from(value: Reading) -> Self {
+        Self {
+            accel: value.accel_mg().map(|mg| (mg * 1024 / 1000) as i16),
+            gyro: [0; 3],
+            compass: value.mag_nt().map(|nt| (nt * 16 / 1000) as i16),
+        }
+    }
+}
+
+impl_fixedgattvalue!(TmsRaw);
+
+/// Quaternion w, x, y, z in Q2.30
+#[repr(C, packed)]
+#[derive(Default, Clone, Copy)]
+pub struct TmsQuaternion {
+    w: i32,
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+impl From<Quaternion> for TmsQuaternion {
+    fn from(value: Quaternion) -> Self {
+        let [w, x, y, z] = value.0.map(|v| (v * (1 << 30) as f32) as i32);
+        Self { w, x, y, z }
+    }
+}
+
+impl_fixedgattvalue!(TmsQuaternion);
+
+/// Roll, pitch and yaw in Q16.16 degrees
+#[repr(C, packed)]
+#[derive(Default, Clone, Copy)]
+pub struct TmsEuler {
+    roll: i32,
+    pitch: i32,
+    yaw: i32,
+}
+
+impl From<Quaternion> for TmsEuler {
+    fn from(value: Quaternion) -> Self {
+        let [roll, pitch, yaw] = value.euler_deg().map(|v| (v * 65536.0) as i32);
+        Self { roll, pitch, yaw }
+    }
+}
+
+impl_fixedgattvalue!(TmsEuler);
+
+/// Row-major 3x3 rotation matrix in Q2.14
+#[repr(C, packed)]
+#[derive(Default, Clone, Copy)]
+pub struct TmsRotationMatrix {
+    matrix: [[i16; 3]; 3],
+}
+
+impl From<Quaternion> for TmsRotationMatrix {
+    fn from(value: Quaternion) -> Self {
+        Self {
+            matrix: value
+                .rotation_matrix()
+                .map(|row| row.map(|v| (v * (1 << 14) as f32) as i16)),
+        }
+    }
+}
+
+impl_fixedgattvalue!(TmsRotationMatrix);
+
 /// Gravity expected to be in units of m/s^2
 #[repr(C, packed)]
 #[derive(Default, Clone, Copy)]
@@ -1,4 +1,8 @@
-use trouble_host::{prelude::*, types::gatt_traits::FromGattError};
+use microbit_co2_core::impl_fixedgattvalue;
+use trouble_host::{
+    prelude::*,
+    types::gatt_traits::{AsGatt, FromGatt, FromGattError},
+};
 
 use super::{ThingyUuid, as_bytes};
 
@@ -12,7 +16,7 @@ const TSS_MICROPHONE: ThingyUuid = ThingyUuid(0x0504);
 #[gatt_service(uuid = TSS)]
 pub struct ThingySoundService {
     #[characteristic(uuid = TSS_CONFIG, read, write)]
-    pub configuration: [u8; 8],
+    pub configuration: TssConfiguration,
     #[characteristic(uuid = TSS_SPEAKER, write_without_response)]
     pub speaker: TssSpeaker,
     #[characteristic(uuid = TSS_SPEAKER_STATUS, notify)]
@@ -22,31 +26,65 @@ pub struct ThingySoundService {
 }
 
 #[repr(C, packed)]
-pub struct TssSpeaker {
-    pcm: [u8; 273],
+#[derive(Clone, Copy)]
+pub struct TssConfiguration {
+    pub speaker_mode: u8,
+    pub microphone_mode: u8,
 }
 
-impl Default for TssSpeaker {
+impl Default for TssConfiguration {
     fn default() -> Self {
-        Self { pcm: [0; 273] }
+        Self {
+            speaker_mode: 0x01,
+            microphone_mode: 0x01,
+        }
     }
 }
 
-impl FixedGattValue for TssSpeaker {
-    const SIZE: usize = size_of::<Self>();
+impl_fixedgattvalue!(TssConfiguration);
 
-    fn from_gatt(data: &[u8]) -> Result<Self, FromGattError> {
-        if data.len() != Self::SIZE {
-            Err(FromGattError::InvalidLength)
-        } else {
-            Ok(Self {
-                pcm: data.try_into().unwrap(),
-            })
+/// Speaker write, its length depends on the speaker mode: 5 bytes for a tone,
+/// 1 byte for a sample ID, up to 273 bytes of 8-bit PCM
+pub struct TssSpeaker {
+    data: [u8; 273],
+    len: u16,
+}
+
+impl TssSpeaker {
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl Default for TssSpeaker {
+    fn default() -> Self {
+        Self {
+            data: [0; 273],
+            len: 0,
         }
     }
+}
+
+impl AsGatt for TssSpeaker {
+    const MIN_SIZE: usize = 1;
+    const MAX_SIZE: usize = 273;
 
     fn as_gatt(&self) -> &[u8] {
-        as_bytes(self)
+        self.data()
+    }
+}
+
+impl FromGatt for TssSpeaker {
+    fn from_gatt(data: &[u8]) -> Result<Self, FromGattError> {
+        if data.is_empty() || data.len() > Self::MAX_SIZE {
+            return Err(FromGattError::InvalidLength);
+        }
+        let mut speaker = Self {
+            len: data.len() as u16,
+            ..Default::default()
+        };
+        speaker.data[..data.len()].copy_from_slice(data);
+        Ok(speaker)
     }
 }
 
@@ -55,6 +93,12 @@ pub struct TssMicrophone {
     raw: [u8; 131],
 }
 
+impl TssMicrophone {
+    pub const fn new(raw: [u8; 131]) -> Self {
+        Self { raw }
+    }
+}
+
 impl Default for TssMicrophone {
     fn default() -> Self {
         Self { raw: [0; 131] }
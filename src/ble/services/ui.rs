@@ -1,4 +1,9 @@
-use trouble_host::prelude::*;
+use trouble_host::{
+    prelude::*,
+    types::gatt_traits::{AsGatt, FromGatt, FromGattError},
+};
+
+use crate::display::Led;
 
 use super::ThingyUuid;
 
@@ -12,8 +17,53 @@ const TUS_PIN: ThingyUuid = ThingyUuid(0x0303);
 pub struct ThingyUiService {
     #[characteristic(uuid = TUS_BUTTON, notify)]
     pub button: u8,
-    #[characteristic(uuid = TUS_LED, read, write, value = 0)]
-    pub led: u64,
+    #[characteristic(uuid = TUS_LED, read, write)]
+    pub led: TusLed,
     #[characteristic(uuid = TUS_PIN, read, write, value = 0)]
     pub pin: u32,
 }
+
+/// Variable length LED characteristic: mode byte plus 0..=4 parameter bytes
+#[derive(Clone, Copy)]
+pub struct TusLed {
+    data: [u8; 5],
+    len: u8,
+}
+
+impl TusLed {
+    pub fn led(&self) -> Led {
+        // from_gatt 已经校验过
+        Led::parse(&self.data[..self.len as usize]).unwrap_or(Led::Off)
+    }
+}
+
+impl Default for TusLed {
+    fn default() -> Self {
+        Led::Off.into()
+    }
+}
+
+impl From<Led> for TusLed {
+    fn from(value: Led) -> Self {
+        let mut data = [0; 5];
+        let len = value.encode(&mut data) as u8;
+        Self { data, len }
+    }
+}
+
+impl AsGatt for TusLed {
+    const MIN_SIZE: usize = 1;
+    const MAX_SIZE: usize = 5;
+
+    fn as_gatt(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl FromGatt for TusLed {
+    fn from_gatt(data: &[u8]) -> Result<Self, FromGattError> {
+        Led::parse(data)
+            .map(Into::into)
+            .map_err(|_| FromGattError::InvalidLength)
+    }
+}
@@ -0,0 +1,117 @@
+use embassy_embedded_hal::shared_bus::{I2cDeviceError, asynch::i2c::I2cDevice};
+use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, mutex::Mutex};
+use embassy_time::{Duration, with_timeout};
+use embedded_hal_async::i2c::{Error, ErrorKind, ErrorType, I2c, Operation};
+use microbit_bsp::embassy_nrf::{
+    Peri, bind_interrupts,
+    peripherals::{P0_08, P0_16, P0_26, P1_00, TWISPI0, TWISPI1},
+    twim::{self, Twim},
+};
+use static_cell::{ConstStaticCell, StaticCell};
+
+// 单次传输的超时，设备拉住总线时释放锁，避免拖死其他设备
+const TRANSACTION_TIMEOUT: Duration = Duration::from_millis(100);
+
+pub type I2cBus = Mutex<ThreadModeRawMutex, Twim<'static>>;
+
+bind_interrupts!(struct Irqs {
+    TWISPI0 => twim::InterruptHandler<TWISPI0>;
+    TWISPI1 => twim::InterruptHandler<TWISPI1>;
+});
+
+/// Edge-connector bus on P19/P20, where the CO2 sensor is wired
+pub fn external(
+    twi: Peri<'static, TWISPI0>,
+    sda: Peri<'static, P1_00>,
+    scl: Peri<'static, P0_26>,
+) -> &'static I2cBus {
+    static RAM_BUFFER: ConstStaticCell<[u8; 16]> = ConstStaticCell::new([0; 16]);
+    static BUS: StaticCell<I2cBus> = StaticCell::new();
+    let i2c = Twim::new(twi, Irqs, sda, scl, Default::default(), RAM_BUFFER.take());
+    BUS.init(Mutex::new(i2c))
+}
+
+/// On-board bus of the LSM303AGR
+pub fn internal(
+    twi: Peri<'static, TWISPI1>,
+    sda: Peri<'static, P0_16>,
+    scl: Peri<'static, P0_08>,
+) -> &'static I2cBus {
+    static RAM_BUFFER: ConstStaticCell<[u8; 16]> = ConstStaticCell::new([0; 16]);
+    static BUS: StaticCell<I2cBus> = StaticCell::new();
+    let i2c = Twim::new(twi, Irqs, sda, scl, Default::default(), RAM_BUFFER.take());
+    BUS.init(Mutex::new(i2c))
+}
+
+#[derive(Debug, defmt::Format)]
+pub enum DeviceError {
+    Bus(twim::Error),
+    Timeout,
+}
+
+impl Error for DeviceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Bus(e) => e.kind(),
+            Self::Timeout => ErrorKind::Other,
+        }
+    }
+}
+
+/// One device on a shared bus.
+///
+/// The bus is only locked for the duration of a transaction, and a
+/// transaction that doesn't complete in time is aborted, so a misbehaving
+/// device only fails its own driver.
+pub struct Device {
+    inner: I2cDevice<'static, ThreadModeRawMutex, Twim<'static>>,
+    name: &'static str,
+    errors: u32,
+}
+
+impl Device {
+    pub fn new(bus: &'static I2cBus, name: &'static str) -> Self {
+        Self {
+            inner: I2cDevice::new(bus),
+            name,
+            errors: 0,
+        }
+    }
+
+    /// Errors since the last successful transaction
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+impl ErrorType for Device {
+    type Error = DeviceError;
+}
+
+impl I2c for Device {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = match with_timeout(
+            TRANSACTION_TIMEOUT,
+            self.inner.transaction(address, operations),
+        )
+        .await
+        {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(I2cDeviceError::I2c(e))) => Err(DeviceError::Bus(e)),
+            Ok(Err(I2cDeviceError::Config)) => unreachable!(),
+            Err(_) => Err(DeviceError::Timeout),
+        };
+        match &result {
+            Ok(()) => self.errors = 0,
+            Err(e) => {
+                self.errors += 1;
+                defmt::debug!("[bus] {} error #{}: {:?}", self.name, self.errors, e);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,70 @@
+use embassy_futures::select::select;
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    watch::{DynReceiver, Watch},
+};
+use embassy_time::{Instant, Timer};
+use microbit_bsp::Button;
+use microbit_co2_core::button::{
+    BUTTON_A, BUTTON_B, CalibrationConfirm, Confirmation, Debouncer, Gesture,
+};
+
+use crate::{display, sense, sound};
+
+// 按键状态消费者数量，分别是 ble
+const STATE_CONSUMERS: usize = 1;
+static STATE: Watch<ThreadModeRawMutex, u8, STATE_CONSUMERS> = Watch::new();
+
+const POLL_MS: u64 = 10;
+
+// 室外空气的参考浓度，用于强制校准
+const CALIBRATION_PPM: u16 = 420;
+
+/// Debounced button state, bit 0 is A and bit 1 is B. Zero means released.
+pub fn get_state_receiver() -> Option<DynReceiver<'static, u8>> {
+    STATE.dyn_receiver()
+}
+
+#[embassy_executor::task]
+pub async fn button_task(mut btn_a: Button, mut btn_b: Button) {
+    let tx = STATE.sender();
+    let mut debouncer = Debouncer::default();
+    let mut confirm = CalibrationConfirm::default();
+    loop {
+        if debouncer.is_idle() {
+            select(btn_a.wait_for_low(), btn_b.wait_for_low()).await;
+        } else {
+            Timer::after_millis(POLL_MS).await;
+        }
+
+        let raw = (btn_a.is_low() as u8 * BUTTON_A) | (btn_b.is_low() as u8 * BUTTON_B);
+        let now_ms = Instant::now().as_millis() as u32;
+        let update = debouncer.update(raw, now_ms);
+        if let Some(state) = update.state {
+            tx.send(state);
+        }
+        if let Some(gesture) = update.gesture {
+            defmt::info!("[button] {:?}", gesture);
+            on_gesture(gesture, confirm.update(gesture, now_ms));
+        }
+    }
+}
+
+fn on_gesture(gesture: Gesture, confirmation: Confirmation) {
+    match confirmation {
+        Confirmation::Prompt => display::send(display::Command::ConfirmCalibration),
+        Confirmation::Calibrate => {
+            sense::calibrate(CALIBRATION_PPM);
+            display::send(display::Command::Calibrating);
+        }
+        Confirmation::Cancelled => defmt::info!("[button] calibration cancelled"),
+        // 报警响起时任意短按只用于贪睡
+        Confirmation::Pass if matches!(gesture, Gesture::Short(_)) && sound::snooze() => {}
+        Confirmation::Pass => match gesture {
+            Gesture::Short(BUTTON_A) => display::send(display::Command::NextMode),
+            Gesture::Short(BUTTON_B) => display::send(display::Command::ToggleOff),
+            Gesture::Short(_) => display::send(display::Command::Reset),
+            Gesture::Long(_) => {}
+        },
+    }
+}
@@ -0,0 +1,26 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_time::Instant;
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+const UNSET: u32 = u32::MAX;
+
+// 设置时刻的本地时间与开机时间之差 (秒)，没有 RTC，由 BLE 写入
+static OFFSET_S: AtomicU32 = AtomicU32::new(UNSET);
+
+/// Set the local wall-clock time as seconds since midnight
+pub fn set_time_of_day(seconds: u32) {
+    let uptime = (Instant::now().as_secs() % SECONDS_PER_DAY as u64) as u32;
+    let offset = (seconds % SECONDS_PER_DAY + SECONDS_PER_DAY - uptime) % SECONDS_PER_DAY;
+    OFFSET_S.store(offset, Ordering::Relaxed);
+}
+
+/// Local minutes since midnight, `None` until the time has been set
+pub fn minute_of_day() -> Option<u16> {
+    let offset = OFFSET_S.load(Ordering::Relaxed);
+    if offset == UNSET {
+        return None;
+    }
+    let uptime = (Instant::now().as_secs() % SECONDS_PER_DAY as u64) as u32;
+    Some((((uptime + offset) % SECONDS_PER_DAY) / 60) as u16)
+}
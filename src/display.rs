@@ -1,37 +1,277 @@
-use core::fmt::Write;
-use embassy_time::Duration;
-use heapless::String;
+use embassy_futures::select::{Either, select};
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    channel::Channel,
+    watch::{DynReceiver, Watch},
+};
+use embassy_time::{Duration, Instant, Timer};
 use microbit_bsp::{
-    display::{Bitmap, Frame, LedMatrix},
+    display::{Bitmap, Brightness, Frame, LedMatrix},
     embassy_nrf::gpio::Output,
 };
+use microbit_co2_core::{
+    display::{Glyph, font, led, mode, transform},
+    format,
+};
+
+use crate::{
+    air_quality,
+    motion::{self, Orientation},
+    sense,
+};
 
-use crate::sense;
+pub use format::Metric;
+pub use led::Led;
+pub use mode::{DisplayMode, ModeManager};
+use transform::Rotation;
 
 const ROWS: usize = 5;
 const COLS: usize = 5;
 
+type Matrix = LedMatrix<Output<'static>, ROWS, COLS>;
+
+// LED 覆盖模式下每帧的刷新时长
+const LED_FRAME: Duration = Duration::from_millis(50);
+// 滚动数字之后柱状图的显示时长
+const BAR_DURATION: Duration = Duration::from_secs(6);
+// 滚动数字之后趋势箭头的显示时长
+const ARROW_DURATION: Duration = Duration::from_secs(1);
+// 滚动露点之后舒适度表情的显示时长
+const COMFORT_DURATION: Duration = Duration::from_secs(2);
+// 滚动文字每移动一列的时长
+const SCROLL_STEP: Duration = Duration::from_millis(90);
+// 启用自动休眠时，无操作多久后熄屏
+const AUTO_SLEEP: Duration = Duration::from_secs(30);
+
+static COMMANDS: Channel<ThreadModeRawMutex, Command, 4> = Channel::new();
+
+// 显示模式消费者数量，分别是 ble
+const MODE_CONSUMERS: usize = 1;
+static MODE: Watch<ThreadModeRawMutex, DisplayMode, MODE_CONSUMERS> = Watch::new();
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Command {
+    /// TUS_LED 写入，覆盖当前显示
+    Led(Led),
+    SetMode(DisplayMode),
+    NextMode,
+    ToggleOff,
+    /// 回到 CO2 显示并点亮
+    Reset,
+    /// 长按 A+B 后等待短按 A 确认校准
+    ConfirmCalibration,
+    Calibrating,
+    /// 敲击或摇晃，从自动休眠中唤醒
+    Wake,
+    /// 启用或关闭无操作自动熄屏
+    AutoSleep(bool),
+}
+
+pub fn send(command: Command) {
+    if COMMANDS.try_send(command).is_err() {
+        defmt::warn!("[display] command dropped: {:?}", command);
+    }
+}
+
+pub fn set_led(led: Led) {
+    send(Command::Led(led));
+}
+
+pub fn get_mode_receiver() -> Option<DynReceiver<'static, DisplayMode>> {
+    MODE.dyn_receiver()
+}
+
+/// The LED matrix with frames rotated to match how the board is held
+struct Screen {
+    matrix: Matrix,
+    rx_orientation: DynReceiver<'static, Orientation>,
+}
+
+impl Screen {
+    fn rotation(&mut self) -> Rotation {
+        self.rx_orientation
+            .try_get()
+            .map(Rotation::from)
+            .unwrap_or_default()
+    }
+
+    async fn show(&mut self, pixels: Glyph, duration: Duration) {
+        let pixels = transform::rotate(pixels, self.rotation());
+        // Bitmap 的最高位是最左边一列，与 Glyph 一致
+        let frame = Frame::new(pixels.map(|row| Bitmap::new(row, COLS)));
+        self.matrix.display(frame, duration).await;
+    }
+
+    async fn scroll(&mut self, text: &str) {
+        for offset in 0..font::scroll_len(text) {
+            self.show(font::scroll_window(text, offset), SCROLL_STEP)
+                .await;
+        }
+    }
+
+    // 从底部开始点亮 level 行
+    async fn show_bar(&mut self, level: usize, duration: Duration) {
+        let mut pixels = [0u8; ROWS];
+        for row in pixels.iter_mut().rev().take(level) {
+            *row = 0b0001_1111;
+        }
+        self.show(pixels, duration).await;
+    }
+}
+
 #[embassy_executor::task]
-pub async fn display_task(mut matrix: LedMatrix<Output<'static>, ROWS, COLS>) {
-    let mut rx = sense::get_co2_receiver().unwrap();
-    let mut txt: String<6> = String::new();
+pub async fn display_task(matrix: Matrix) {
+    let mut rx_co2 = sense::get_co2_receiver().unwrap();
+    let mut rx_averages = sense::get_averages_receiver().unwrap();
+    let mut rx_temperature = sense::get_temperature_receiver().unwrap();
+    let mut rx_humidity = sense::get_humidity_receiver().unwrap();
+    let mut rx_climate = sense::get_climate_receiver().unwrap();
+    let mut rx_air_quality = air_quality::get_status_receiver().unwrap();
+    let mut screen = Screen {
+        matrix,
+        rx_orientation: motion::get_orientation_receiver().unwrap(),
+    };
+
+    let tx_mode = MODE.sender();
+    let mut modes = ModeManager::default();
+    tx_mode.send(modes.mode());
+
+    let mut carousel = Metric::Co2;
+    let mut pending = None;
+    let mut auto_sleep = false;
+    let mut asleep = false;
+    let mut last_activity = Instant::now();
     loop {
-        let co2 = rx.get().await;
-        write!(&mut txt, " {}", co2).ok();
-        matrix.scroll(txt.as_str()).await;
-        txt.clear();
-        // Timer::after_millis(2000).await;
-
-        let mut level = [Bitmap::empty(COLS); ROWS];
-        for (i, row) in level.iter_mut().rev().enumerate() {
-            if (co2 as usize) > (400 + 200 * i) {
-                *row = Bitmap::new(0b0001_1111, COLS);
-            } else {
+        if auto_sleep
+            && !asleep
+            && modes.mode() != DisplayMode::Off
+            && last_activity.elapsed() >= AUTO_SLEEP
+        {
+            modes.set(DisplayMode::Off);
+            asleep = true;
+            tx_mode.send(modes.mode());
+            blank(&mut screen.matrix).await;
+        }
+
+        let command = match pending.take() {
+            Some(command) => command,
+            None => {
+                let show = async {
+                    let metric = match modes.mode() {
+                        DisplayMode::Co2 => Metric::Co2,
+                        DisplayMode::Temperature => Metric::Temperature,
+                        DisplayMode::Humidity => Metric::Humidity,
+                        DisplayMode::DewPoint => Metric::DewPoint,
+                        DisplayMode::Carousel => {
+                            let metric = carousel;
+                            carousel = match carousel {
+                                Metric::Co2 => Metric::Temperature,
+                                Metric::Temperature => Metric::Humidity,
+                                Metric::Humidity | Metric::DewPoint => Metric::Co2,
+                            };
+                            metric
+                        }
+                        DisplayMode::BarOnly => {
+                            let level = rx_air_quality.get().await.bar_level();
+                            screen.show_bar(level, Duration::from_secs(1)).await;
+                            return;
+                        }
+                        DisplayMode::Off => return core::future::pending().await,
+                    };
+
+                    match metric {
+                        Metric::Co2 => {
+                            let co2 = rx_co2.get().await;
+                            let txt = format::reading_text(metric, co2 as i32);
+                            screen.scroll(txt.as_str()).await;
+                            if let Some(averages) = rx_averages.try_get() {
+                                let arrow = font::trend_arrow(averages.trend);
+                                screen.show(arrow, ARROW_DURATION).await;
+                            }
+                            let level = rx_air_quality.get().await.bar_level();
+                            screen.show_bar(level, BAR_DURATION).await;
+                        }
+                        Metric::Temperature => {
+                            let value = rx_temperature.get().await as i32;
+                            screen
+                                .scroll(format::reading_text(metric, value).as_str())
+                                .await;
+                            Timer::after_secs(1).await;
+                        }
+                        Metric::Humidity => {
+                            let value = rx_humidity.get().await as i32;
+                            screen
+                                .scroll(format::reading_text(metric, value).as_str())
+                                .await;
+                            Timer::after_secs(1).await;
+                        }
+                        Metric::DewPoint => {
+                            let climate = rx_climate.get().await;
+                            let value = climate.dew_point_c as i32;
+                            screen
+                                .scroll(format::reading_text(metric, value).as_str())
+                                .await;
+                            let face = font::comfort_face(climate.comfort);
+                            screen.show(face, COMFORT_DURATION).await;
+                        }
+                    }
+                };
+                match select(show, COMMANDS.receive()).await {
+                    Either::First(_) => continue,
+                    Either::Second(command) => command,
+                }
+            }
+        };
+
+        defmt::debug!("[display] {:?}", command);
+        last_activity = Instant::now();
+        match command {
+            Command::Led(led) => pending = show_led(&mut screen.matrix, led).await,
+            Command::SetMode(mode) => modes.set(mode),
+            Command::NextMode => modes.next(),
+            Command::ToggleOff => modes.toggle_off(),
+            Command::Reset => modes.set(DisplayMode::Co2),
+            Command::ConfirmCalibration => screen.scroll(" CAL?").await,
+            Command::Calibrating => screen.scroll(" CAL").await,
+            // 只唤醒自动休眠，用户手动关闭的显示保持关闭
+            Command::Wake if asleep => modes.wake(),
+            Command::Wake => {}
+            Command::AutoSleep(enabled) => auto_sleep = enabled,
+        }
+        // 任何命令之后，关闭状态都视为用户选择
+        asleep = asleep && modes.mode() == DisplayMode::Off;
+        carousel = Metric::Co2;
+        tx_mode.send(modes.mode());
+        blank(&mut screen.matrix).await;
+    }
+}
+
+// 全屏点亮并按 LED 模式调节亮度，直到模式结束或收到其他命令
+async fn show_led(matrix: &mut Matrix, mut led: Led) -> Option<Command> {
+    let full = Frame::new([Bitmap::new(0b0001_1111, COLS); ROWS]);
+    let mut start = Instant::now();
+    let mut pending = None;
+    while let Some(level) = led.level(start.elapsed().as_millis() as u32) {
+        matrix.set_brightness(Brightness::new(level));
+        match select(matrix.display(full, LED_FRAME), COMMANDS.receive()).await {
+            Either::First(_) => {}
+            Either::Second(Command::Led(next)) => {
+                led = next;
+                start = Instant::now();
+            }
+            Either::Second(command) => {
+                pending = Some(command);
                 break;
             }
         }
-        matrix
-            .display(Frame::new(level), Duration::from_secs(6))
-            .await;
     }
+    matrix.set_brightness(Brightness::default());
+    pending
+}
+
+// 矩阵是逐行扫描的，停止刷新前显示一帧空白，避免最后一行常亮
+async fn blank(matrix: &mut Matrix) {
+    matrix
+        .display(Frame::new([Bitmap::empty(COLS); ROWS]), LED_FRAME)
+        .await;
 }
@@ -1,13 +1,26 @@
 #![no_std]
 #![no_main]
 
+mod air_quality;
 mod ble;
+mod bus;
+mod button;
+mod clock;
 mod display;
+mod microphone;
+mod motion;
+mod occupancy;
+mod pins;
 mod sense;
+mod sound;
+mod storage;
 
 use defmt_rtt as _;
 use embassy_executor::Spawner;
-use microbit_bsp::Microbit;
+use microbit_bsp::{
+    Microbit,
+    embassy_nrf::gpio::{Level, Output, OutputDrive},
+};
 use panic_probe as _;
 
 #[embassy_executor::main]
@@ -15,8 +28,28 @@ async fn main(spawner: Spawner) {
     defmt::info!("Starting...");
     // let p = embassy_nrf::init(Default::default());
     let b = Microbit::default();
-    spawner.must_spawn(sense::sense_task(b.twispi0, b.p20, b.p19));
+    let external = bus::external(b.twispi0, b.p20, b.p19);
+    let internal = bus::internal(b.twispi1, b.i2c_int_sda, b.i2c_int_scl);
+    spawner.must_spawn(sense::sense_task(external));
+    spawner.must_spawn(motion::motion_task(bus::Device::new(internal, "lsm303agr")));
+    spawner.must_spawn(motion::tap_task(
+        bus::Device::new(internal, "lsm303agr-tap"),
+        motion::take_interrupt(),
+    ));
+    spawner.must_spawn(air_quality::air_quality_task());
+    spawner.must_spawn(occupancy::occupancy_task());
     spawner.must_spawn(display::display_task(b.display));
+    spawner.must_spawn(button::button_task(b.btn_a, b.btn_b));
+    let outputs = [
+        Output::new(b.p0, Level::Low, OutputDrive::Standard),
+        Output::new(b.p1, Level::Low, OutputDrive::Standard),
+        Output::new(b.p2, Level::Low, OutputDrive::Standard),
+        Output::new(b.p16, Level::Low, OutputDrive::Standard),
+    ];
+    spawner.must_spawn(pins::pin_task(outputs));
+    spawner.must_spawn(sound::sound_task(b.pwm0, b.speaker));
+    spawner.must_spawn(microphone::microphone_task(b.saadc, b.microphone, b.micen));
+    let address = ble::address::local_address();
     let (sdc, mpsl) = b.ble.init(b.timer0, b.rng).unwrap();
-    ble::run(sdc, mpsl, spawner).await;
+    ble::run(sdc, mpsl, address, spawner).await;
 }
@@ -0,0 +1,93 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    channel::{Channel, Receiver},
+    watch::{DynReceiver, Watch},
+};
+use microbit_bsp::embassy_nrf::{
+    Peri, bind_interrupts,
+    gpio::{Level, Output, OutputDrive},
+    peripherals::{P0_05, P0_20, SAADC},
+    saadc::{self, CallbackResult, ChannelConfig, Saadc},
+};
+
+pub use microbit_co2_core::microphone::{
+    adpcm::{AdpcmEncoder, FRAME_SIZE},
+    level::SoundLevelMeter,
+};
+
+pub const SAMPLE_RATE_HZ: u32 = 16_000;
+// SAADC 内部定时器以 16MHz 为时钟
+const SAMPLE_RATE_DIVISOR: u16 = (16_000_000 / SAMPLE_RATE_HZ) as u16;
+const BLOCK_SAMPLES: usize = 256;
+const LEVEL_WINDOW_MS: u32 = 1000;
+
+// 声级消费者数量，分别是 ble
+const LEVEL_CONSUMERS: usize = 1;
+static LEVEL: Watch<ThreadModeRawMutex, u8, LEVEL_CONSUMERS> = Watch::new();
+
+static STREAMING: AtomicBool = AtomicBool::new(false);
+static FRAMES: Channel<ThreadModeRawMutex, [u8; FRAME_SIZE], 2> = Channel::new();
+
+/// Sound level in dB SPL, updated once per second
+pub fn get_level_receiver() -> Option<DynReceiver<'static, u8>> {
+    LEVEL.dyn_receiver()
+}
+
+/// ADPCM frames are only produced while streaming is enabled
+pub fn set_streaming(enabled: bool) {
+    STREAMING.store(enabled, Ordering::Relaxed);
+}
+
+pub fn frames() -> Receiver<'static, ThreadModeRawMutex, [u8; FRAME_SIZE], 2> {
+    FRAMES.receiver()
+}
+
+#[embassy_executor::task]
+pub async fn microphone_task(
+    saadc: Peri<'static, SAADC>,
+    mic: Peri<'static, P0_05>,
+    micen: Peri<'static, P0_20>,
+) {
+    bind_interrupts!(struct Irqs {
+        SAADC => saadc::InterruptHandler;
+    });
+    // 麦克风由 RUN_MIC 引脚供电
+    let _micen = Output::new(micen, Level::High, OutputDrive::HighDrive);
+    let mut saadc = Saadc::new(
+        saadc,
+        Irqs,
+        Default::default(),
+        [ChannelConfig::single_ended(mic)],
+    );
+    saadc.calibrate().await;
+
+    let tx_level = LEVEL.sender();
+    let mut meter = SoundLevelMeter::new(SAMPLE_RATE_HZ, LEVEL_WINDOW_MS);
+    let mut encoder = AdpcmEncoder::new();
+    let mut bufs = [[[0i16; 1]; BLOCK_SAMPLES]; 2];
+    // 12 位 ADC 的直流偏置，缓慢跟踪
+    let mut dc = 2048i32 << 8;
+    saadc
+        .run_timer_sampler(&mut bufs, SAMPLE_RATE_DIVISOR, |block| {
+            let streaming = STREAMING.load(Ordering::Relaxed);
+            for &[raw] in block {
+                dc += raw as i32 - (dc >> 8);
+                let sample = ((raw as i32 - (dc >> 8)) << 4).clamp(i16::MIN as i32, i16::MAX as i32)
+                    as i16;
+
+                if let Some(db) = meter.push(sample) {
+                    tx_level.send(db as u8);
+                }
+                if streaming {
+                    if let Some(frame) = encoder.push(sample) {
+                        // BLE 来不及发送时丢帧
+                        FRAMES.try_send(frame).ok();
+                    }
+                }
+            }
+            CallbackResult::Continue
+        })
+        .await;
+}
@@ -0,0 +1,445 @@
+pub mod tap;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_futures::select::{Either, select};
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    signal::Signal,
+    watch::{DynReceiver, Watch},
+};
+use embassy_time::{Delay, Duration, Instant, Ticker, Timer};
+use embedded_hal_async::i2c::I2c;
+use microbit_bsp::{
+    embassy_nrf::{
+        gpio::{Input, Pull},
+        peripherals::P0_25,
+    },
+    lsm303agr::{
+        AccelMode, AccelOutputDataRate, Acceleration, Lsm303agr, MagMode, MagOutputDataRate,
+        MagneticField,
+    },
+};
+
+use microbit_co2_core::motion::{fusion, heading, orientation, pedometer};
+
+use crate::{
+    ble::services::monitor::CmsCompassCalibration,
+    bus::Device,
+    display,
+    storage::{self, Key},
+};
+
+pub use fusion::Quaternion;
+pub use heading::Calibration;
+pub use orientation::Orientation;
+pub use pedometer::StepConfig;
+pub use tap::TapEvent;
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct MotionConfig {
+    pub pedometer_interval_ms: u16,
+    pub compass_interval_ms: u16,
+    pub motion_frequency_hz: u16,
+    pub wake_on_motion: bool,
+    pub step: StepConfig,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        Self {
+            pedometer_interval_ms: 1000,
+            compass_interval_ms: 500,
+            motion_frequency_hz: 10,
+            wake_on_motion: false,
+            step: StepConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Reading {
+    pub accel: Acceleration,
+    pub mag: MagneticField,
+}
+
+impl Reading {
+    pub fn accel_mg(&self) -> [i32; 3] {
+        [self.accel.x_mg(), self.accel.y_mg(), self.accel.z_mg()]
+    }
+
+    pub fn mag_nt(&self) -> [i32; 3] {
+        [self.mag.x_nt(), self.mag.y_nt(), self.mag.z_nt()]
+    }
+}
+
+/// Step count and time since counting started, as in TMS_PEDOMETER
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct Steps {
+    pub steps: u32,
+    pub elapsed_ms: u32,
+}
+
+static CONFIG: Signal<ThreadModeRawMutex, MotionConfig> = Signal::new();
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+enum CompassCommand {
+    Calibrate,
+    Set(Calibration),
+    Clear,
+}
+
+static COMPASS: Signal<ThreadModeRawMutex, CompassCommand> = Signal::new();
+
+// 磁力计校准采集时长，期间需要向各个方向转动板子
+const CALIBRATION_DURATION: Duration = Duration::from_secs(20);
+
+// 新校准结果消费者数量，分别是 ble
+const CALIBRATION_CONSUMERS: usize = 1;
+static CALIBRATION: Watch<ThreadModeRawMutex, Calibration, CALIBRATION_CONSUMERS> = Watch::new();
+
+// 原始数据消费者数量，分别是 ble
+const READING_CONSUMERS: usize = 1;
+static READING: Watch<ThreadModeRawMutex, Reading, READING_CONSUMERS> = Watch::new();
+
+// 航向消费者数量，分别是 ble
+const HEADING_CONSUMERS: usize = 1;
+static HEADING: Watch<ThreadModeRawMutex, i32, HEADING_CONSUMERS> = Watch::new();
+
+// 姿态消费者数量，分别是 ble
+const QUATERNION_CONSUMERS: usize = 1;
+static QUATERNION: Watch<ThreadModeRawMutex, Quaternion, QUATERNION_CONSUMERS> = Watch::new();
+
+// 方向消费者数量，分别是 ble 和 display
+const ORIENTATION_CONSUMERS: usize = 2;
+static ORIENTATION: Watch<ThreadModeRawMutex, Orientation, ORIENTATION_CONSUMERS> = Watch::new();
+
+// 计步消费者数量，分别是 ble
+const STEPS_CONSUMERS: usize = 1;
+static STEPS: Watch<ThreadModeRawMutex, Steps, STEPS_CONSUMERS> = Watch::new();
+
+// 检测到行走，设备正被携带
+static CARRIED: AtomicBool = AtomicBool::new(false);
+
+// 敲击事件消费者数量，分别是 ble
+const TAP_CONSUMERS: usize = 1;
+static TAP: Watch<ThreadModeRawMutex, TapEvent, TAP_CONSUMERS> = Watch::new();
+
+// 加速度计已初始化并设置好输出速率，之后 tap_task 才能写中断寄存器
+static ACCEL_READY: Signal<ThreadModeRawMutex, ()> = Signal::new();
+
+// 摇晃后忽略后续中断的时长
+const SHAKE_HOLDOFF: Duration = Duration::from_secs(1);
+
+pub fn configure(config: MotionConfig) {
+    display::send(display::Command::AutoSleep(config.wake_on_motion));
+    CONFIG.signal(config);
+}
+
+/// Start collecting a new hard/soft-iron calibration
+pub fn calibrate_compass() {
+    COMPASS.signal(CompassCommand::Calibrate);
+}
+
+/// Restore a saved calibration, it isn't published or saved again
+pub fn set_compass_calibration(calibration: Calibration) {
+    COMPASS.signal(CompassCommand::Set(calibration));
+}
+
+pub fn clear_compass_calibration() {
+    COMPASS.signal(CompassCommand::Clear);
+}
+
+/// Calibrations produced by `calibrate_compass` or `clear_compass_calibration`
+pub fn get_calibration_receiver() -> Option<DynReceiver<'static, Calibration>> {
+    CALIBRATION.dyn_receiver()
+}
+
+/// The on-board sensors' shared interrupt line on P0.25.
+///
+/// The BSP doesn't export the pin, so it's stolen here; panics if taken twice.
+pub fn take_interrupt() -> Input<'static> {
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+    assert!(!TAKEN.swap(true, Ordering::Relaxed), "P0.25 already taken");
+    // SAFETY: BSP 和其他模块都不使用 P0.25，上面的检查保证只创建一个实例
+    Input::new(unsafe { P0_25::steal() }, Pull::Up)
+}
+
+pub fn get_tap_receiver() -> Option<DynReceiver<'static, TapEvent>> {
+    TAP.dyn_receiver()
+}
+
+pub fn get_reading_receiver() -> Option<DynReceiver<'static, Reading>> {
+    READING.dyn_receiver()
+}
+
+/// Heading in Q16.16 degrees
+pub fn get_heading_receiver() -> Option<DynReceiver<'static, i32>> {
+    HEADING.dyn_receiver()
+}
+
+pub fn get_quaternion_receiver() -> Option<DynReceiver<'static, Quaternion>> {
+    QUATERNION.dyn_receiver()
+}
+
+pub fn get_orientation_receiver() -> Option<DynReceiver<'static, Orientation>> {
+    ORIENTATION.dyn_receiver()
+}
+
+pub fn get_steps_receiver() -> Option<DynReceiver<'static, Steps>> {
+    STEPS.dyn_receiver()
+}
+
+/// Whether the unit is being carried around, readings taken now aren't
+/// representative of the room
+pub fn is_carried() -> bool {
+    CARRIED.load(Ordering::Relaxed)
+}
+
+// 选择不低于采样频率的加速度计输出速率
+fn accel_odr(frequency_hz: u16) -> AccelOutputDataRate {
+    match frequency_hz {
+        0..=1 => AccelOutputDataRate::Hz1,
+        2..=10 => AccelOutputDataRate::Hz10,
+        11..=25 => AccelOutputDataRate::Hz25,
+        26..=50 => AccelOutputDataRate::Hz50,
+        51..=100 => AccelOutputDataRate::Hz100,
+        _ => AccelOutputDataRate::Hz200,
+    }
+}
+
+fn mag_odr(frequency_hz: u16) -> MagOutputDataRate {
+    match frequency_hz {
+        0..=10 => MagOutputDataRate::Hz10,
+        11..=20 => MagOutputDataRate::Hz20,
+        21..=50 => MagOutputDataRate::Hz50,
+        _ => MagOutputDataRate::Hz100,
+    }
+}
+
+// 只在校准结果变化时写入 flash，与是否有 BLE 连接无关
+fn save_calibration(calibration: Calibration) {
+    storage::save(Key::Compass, &CmsCompassCalibration::from(calibration));
+}
+
+#[embassy_executor::task]
+pub async fn motion_task(i2c: Device) {
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    // 初始化失败时只影响本任务，定期重试
+    while let Err(e) = sensor.init().await {
+        defmt::warn!("[motion] init failed: {:?}", defmt::Debug2Format(&e));
+        Timer::after_secs(5).await;
+    }
+    let mut sensor = match sensor.into_mag_continuous().await {
+        Ok(sensor) => sensor,
+        Err(_) => {
+            defmt::error!("[motion] failed to enter continuous magnetometer mode");
+            return;
+        }
+    };
+
+    let tx_reading = READING.sender();
+    let tx_heading = HEADING.sender();
+    let tx_orientation = ORIENTATION.sender();
+    let tx_steps = STEPS.sender();
+    let tx_calibration = CALIBRATION.sender();
+    let tx_quaternion = QUATERNION.sender();
+    let mut fusion = fusion::Fusion::new();
+    let mut calibration = Calibration::default();
+    let mut calibrator: Option<(heading::Calibrator, Instant)> = None;
+    let mut pedometer = pedometer::Pedometer::new(StepConfig::default());
+    let start = Instant::now();
+    let mut config = MotionConfig::default();
+    loop {
+        let frequency_hz = config.motion_frequency_hz.clamp(1, 200);
+        // 计步需要更高的采样率，原始数据仍按配置的频率发布
+        let sample_hz = pedometer::sample_hz(frequency_hz);
+        let decimation = sample_hz / frequency_hz;
+        let configured = async {
+            sensor
+                .set_accel_mode_and_odr(
+                    &mut Delay,
+                    AccelMode::Normal,
+                    accel_odr(sample_hz.max(tap::TAP_ODR_HZ)),
+                )
+                .await?;
+            sensor
+                .set_mag_mode_and_odr(&mut Delay, MagMode::HighResolution, mag_odr(frequency_hz))
+                .await
+        };
+        if let Err(e) = configured.await {
+            defmt::warn!(
+                "[motion] configuration failed: {:?}",
+                defmt::Debug2Format(&e)
+            );
+            Timer::after_secs(1).await;
+            continue;
+        }
+        ACCEL_READY.signal(());
+
+        let mut ticker = Ticker::every(Duration::from_hz(sample_hz as u64));
+        let compass_interval = Duration::from_millis(config.compass_interval_ms as u64);
+        let pedometer_interval = Duration::from_millis(config.pedometer_interval_ms as u64);
+        let mut last_heading = Instant::MIN;
+        let mut last_steps = Instant::MIN;
+        let mut tick: u16 = 0;
+        loop {
+            if let Either::Second(next) = select(ticker.next(), CONFIG.wait()).await {
+                defmt::info!("[motion] config: {:?}", next);
+                config = next;
+                pedometer.configure(config.step);
+                break;
+            }
+
+            let (accel, mag) = match (sensor.acceleration().await, sensor.magnetic_field().await) {
+                (Ok(accel), Ok(mag)) => (accel, mag),
+                _ => {
+                    defmt::warn!("[motion] read failed");
+                    continue;
+                }
+            };
+            let reading = Reading { accel, mag };
+            let mag_nt = reading.mag_nt().map(|v| v as f32);
+
+            match COMPASS.try_take() {
+                Some(CompassCommand::Calibrate) => {
+                    defmt::info!("[motion] compass calibration started");
+                    display::send(display::Command::Calibrating);
+                    calibrator = Some((heading::Calibrator::default(), Instant::now()));
+                }
+                Some(CompassCommand::Set(value)) => calibration = value,
+                Some(CompassCommand::Clear) if calibration != Calibration::default() => {
+                    calibration = Calibration::default();
+                    save_calibration(calibration);
+                    tx_calibration.send(calibration);
+                }
+                Some(CompassCommand::Clear) | None => {}
+            }
+            if let Some((collecting, started)) = calibrator.as_mut() {
+                collecting.update(mag_nt);
+                if started.elapsed() >= CALIBRATION_DURATION {
+                    match collecting.finish() {
+                        Some(value) if value != calibration => {
+                            defmt::info!("[motion] compass calibrated: {:?}", value);
+                            calibration = value;
+                            save_calibration(calibration);
+                            tx_calibration.send(calibration);
+                        }
+                        Some(_) => defmt::info!("[motion] compass calibration unchanged"),
+                        None => defmt::warn!("[motion] compass calibration incomplete"),
+                    }
+                    calibrator = None;
+                }
+            }
+
+            let now_ms = start.elapsed().as_millis() as u32;
+            // 录制夹具，按文件名过滤输出
+            #[cfg(feature = "capture")]
+            {
+                let [x, y, z] = reading.accel_mg();
+                defmt::println!("walking.csv {},{},{},{}", now_ms, x, y, z);
+            }
+            pedometer.update(reading.accel_mg(), now_ms);
+            let carried = pedometer.carried(now_ms);
+            if CARRIED.swap(carried, Ordering::Relaxed) != carried {
+                defmt::info!("[motion] carried: {}", carried);
+            }
+            if last_steps.elapsed() >= pedometer_interval {
+                last_steps = Instant::now();
+                tx_steps.send_if_modified(|current| {
+                    let steps = pedometer.steps();
+                    let modified = current.is_none_or(|c| c.steps != steps);
+                    *current = Some(Steps {
+                        steps,
+                        elapsed_ms: now_ms,
+                    });
+                    modified
+                });
+            }
+
+            tick += 1;
+            if tick < decimation {
+                continue;
+            }
+            tick = 0;
+            tx_reading.send(reading);
+
+            let accel_mg = reading.accel_mg().map(|v| v as f32);
+            if let Some(quaternion) = fusion.update(accel_mg, calibration.apply(mag_nt)) {
+                tx_quaternion.send(quaternion);
+            }
+
+            if let Some(orientation) = orientation::orientation(reading.accel_mg()) {
+                tx_orientation.send_if_modified(|current| {
+                    let modified = *current != Some(orientation);
+                    *current = Some(orientation);
+                    modified
+                });
+            }
+
+            if last_heading.elapsed() >= compass_interval {
+                last_heading = Instant::now();
+                let mag = calibration.apply(mag_nt);
+                let degrees = heading::tilt_compensated_deg(accel_mg, mag);
+                tx_heading.send(heading::to_q16(degrees));
+            }
+        }
+    }
+}
+
+/// Tap and shake detection on the accelerometer's click and AOI1 interrupts.
+///
+/// Uses its own handle on the shared bus, the LSM303AGR driver in
+/// `motion_task` doesn't expose the interrupt registers. The driver keeps the
+/// data rate, mode and scale registers, this task only writes `tap::CONFIG`,
+/// once `motion_task` has initialised the sensor.
+#[embassy_executor::task]
+pub async fn tap_task(mut i2c: Device, mut int: Input<'static>) {
+    // 驱动初始化时会重写控制寄存器，必须在其之后配置
+    ACCEL_READY.wait().await;
+    for (register, value) in tap::CONFIG.iter().chain([tap::INT_ACTIVE_LOW].iter()) {
+        if let Err(e) = i2c.write(tap::ACCEL_ADDRESS, &[*register, *value]).await {
+            defmt::error!("[tap] configuration failed: {:?}", e);
+            return;
+        }
+    }
+
+    let tx = TAP.sender();
+    let mut last_shake = Instant::MIN;
+    loop {
+        int.wait_for_low().await;
+
+        // 读取源寄存器同时清除锁存的中断
+        let mut click_src = [0u8];
+        let mut int1_src = [0u8];
+        let read = async {
+            i2c.write_read(tap::ACCEL_ADDRESS, &[tap::CLICK_SRC_A], &mut click_src)
+                .await?;
+            i2c.write_read(tap::ACCEL_ADDRESS, &[tap::INT1_SRC_A], &mut int1_src)
+                .await
+        };
+        if let Err(e) = read.await {
+            defmt::warn!("[tap] read failed: {:?}", e);
+            Timer::after_millis(100).await;
+            continue;
+        }
+
+        let shake =
+            tap::decode_shake(int1_src[0]).filter(|_| last_shake.elapsed() >= SHAKE_HOLDOFF);
+        if shake.is_some() {
+            last_shake = Instant::now();
+        }
+        for event in [tap::decode_click(click_src[0]), shake]
+            .into_iter()
+            .flatten()
+        {
+            defmt::info!("[tap] {:?}", event);
+            tx.send(event);
+            display::send(display::Command::Wake);
+        }
+        // 中断线与磁力计等共用，未被清除时避免忙等
+        if int.is_low() {
+            Timer::after_millis(50).await;
+        }
+    }
+}
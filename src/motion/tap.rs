@@ -0,0 +1,58 @@
+// LSM303AGR 加速度计寄存器
+pub const ACCEL_ADDRESS: u8 = 0x19;
+pub const CTRL_REG1_A: u8 = 0x20;
+pub const CTRL_REG3_A: u8 = 0x22;
+pub const CTRL_REG4_A: u8 = 0x23;
+pub const CTRL_REG5_A: u8 = 0x24;
+pub const CTRL_REG6_A: u8 = 0x25;
+pub const INT1_CFG_A: u8 = 0x30;
+pub const INT1_SRC_A: u8 = 0x31;
+pub const INT1_THS_A: u8 = 0x32;
+pub const INT1_DURATION_A: u8 = 0x33;
+pub const CLICK_CFG_A: u8 = 0x38;
+pub const CLICK_SRC_A: u8 = 0x39;
+pub const CLICK_THS_A: u8 = 0x3A;
+pub const TIME_LIMIT_A: u8 = 0x3B;
+pub const TIME_LATENCY_A: u8 = 0x3C;
+pub const TIME_WINDOW_A: u8 = 0x3D;
+
+/// Minimum accelerometer output data rate for click detection, the timing
+/// registers below are in units of this rate
+pub const TAP_ODR_HZ: u16 = 100;
+
+/// Register writes enabling single/double click and shake (high-g on any axis)
+/// on INT1, at ±2 g full scale where one threshold LSB is 16 mg.
+///
+/// Only interrupt and click registers, CTRL_REG1_A and CTRL_REG4_A (data
+/// rate, mode and scale) belong to the driver in `motion_task`.
+pub const CONFIG: [(u8, u8); 10] = [
+    // 单击和双击，XYZ 三轴
+    (CLICK_CFG_A, 0b0011_1111),
+    // 锁存，阈值 48 * 16mg
+    (CLICK_THS_A, 0x80 | 48),
+    (TIME_LIMIT_A, 8),
+    (TIME_LATENCY_A, 16),
+    (TIME_WINDOW_A, 32),
+    // 任意轴高于阈值，阈值 94 * 16mg 约 1.5g
+    (INT1_CFG_A, 0b0010_1010),
+    (INT1_THS_A, 94),
+    (INT1_DURATION_A, 2),
+    // 锁存 INT1
+    (CTRL_REG5_A, 0b0000_1000),
+    // INT1 输出 click 和 AOI1，与其他中断源共用低电平有效的中断线
+    (CTRL_REG3_A, 0b1100_0000),
+];
+
+pub const INT_ACTIVE_LOW: (u8, u8) = (CTRL_REG6_A, 0b0000_0010);
+
+// 编译期检查不会改写驱动持有的寄存器
+const _: () = {
+    let mut i = 0;
+    while i < CONFIG.len() {
+        assert!(CONFIG[i].0 != CTRL_REG1_A && CONFIG[i].0 != CTRL_REG4_A);
+        i += 1;
+    }
+    assert!(INT_ACTIVE_LOW.0 != CTRL_REG1_A && INT_ACTIVE_LOW.0 != CTRL_REG4_A);
+};
+
+pub use microbit_co2_core::motion::tap::{TapDirection, TapEvent, decode_click, decode_shake};
@@ -0,0 +1,50 @@
+use embassy_futures::select::{Either, select};
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    signal::Signal,
+    watch::{DynReceiver, Watch},
+};
+
+use crate::sense;
+
+pub use microbit_co2_core::occupancy::{OccupancyConfig, estimate};
+
+static CONFIG: Signal<ThreadModeRawMutex, OccupancyConfig> = Signal::new();
+
+// 估计人数消费者数量，分别是 ble
+const OCCUPANCY_CONSUMERS: usize = 1;
+static OCCUPANCY: Watch<ThreadModeRawMutex, u8, OCCUPANCY_CONSUMERS> = Watch::new();
+
+pub fn configure(config: OccupancyConfig) {
+    CONFIG.signal(config);
+}
+
+pub fn get_occupancy_receiver() -> Option<DynReceiver<'static, u8>> {
+    OCCUPANCY.dyn_receiver()
+}
+
+#[embassy_executor::task]
+pub async fn occupancy_task() {
+    let mut rx_averages = sense::get_averages_receiver().unwrap();
+    let tx = OCCUPANCY.sender();
+    let mut config = OccupancyConfig::default();
+    loop {
+        let averages = match select(rx_averages.changed(), CONFIG.wait()).await {
+            Either::First(averages) => averages,
+            Either::Second(next) => {
+                defmt::info!("[occupancy] config: {:?}", next);
+                config = next;
+                match rx_averages.try_get() {
+                    Some(averages) => averages,
+                    None => continue,
+                }
+            }
+        };
+        let people = estimate(&config, averages.avg_1min, averages.rate_ppm_per_min as f32);
+        tx.send_if_modified(|current| {
+            let modified = *current != Some(people);
+            *current = Some(people);
+            modified
+        });
+    }
+}
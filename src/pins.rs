@@ -0,0 +1,86 @@
+use embassy_futures::select::{Either3, select3};
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    signal::Signal,
+    watch::{DynReceiver, Watch},
+};
+use embassy_time::Instant;
+use microbit_bsp::embassy_nrf::gpio::{Level, Output};
+
+use crate::sense;
+
+pub use microbit_co2_core::ventilation::{Ventilation, VentilationConfig};
+
+/// Number of edge-connector outputs exposed through TUS_PIN: P0, P1, P2 and P16
+pub const PINS: usize = 4;
+
+// TUS_PIN 每个字节对应一个引脚，非零为高电平
+static WRITE: Signal<ThreadModeRawMutex, [u8; PINS]> = Signal::new();
+static CONFIG: Signal<ThreadModeRawMutex, VentilationConfig> = Signal::new();
+
+// 引脚实际状态消费者数量，分别是 ble
+const STATE_CONSUMERS: usize = 1;
+static STATE: Watch<ThreadModeRawMutex, [u8; PINS], STATE_CONSUMERS> = Watch::new();
+
+pub fn write(states: [u8; PINS]) {
+    WRITE.signal(states);
+}
+
+/// The ventilation config names an output that doesn't exist or has its
+/// thresholds reversed
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct InvalidConfig;
+
+pub fn configure_ventilation(config: VentilationConfig) -> Result<(), InvalidConfig> {
+    if !config.is_valid(PINS) {
+        defmt::warn!("[pins] invalid ventilation config: {:?}", config);
+        return Err(InvalidConfig);
+    }
+    CONFIG.signal(config);
+    Ok(())
+}
+
+pub fn get_state_receiver() -> Option<DynReceiver<'static, [u8; PINS]>> {
+    STATE.dyn_receiver()
+}
+
+fn now_s() -> u32 {
+    Instant::now().as_secs() as u32
+}
+
+#[embassy_executor::task]
+pub async fn pin_task(mut outputs: [Output<'static>; PINS]) {
+    let mut rx_co2 = sense::get_co2_receiver().unwrap();
+    let tx = STATE.sender();
+    let mut ventilation = Ventilation::new(VentilationConfig::default());
+    let mut manual = [0u8; PINS];
+    loop {
+        match select3(rx_co2.changed(), WRITE.wait(), CONFIG.wait()).await {
+            Either3::First(co2) => {
+                ventilation.update(co2, now_s());
+            }
+            Either3::Second(states) => manual = states,
+            Either3::Third(config) => {
+                defmt::info!("[pins] ventilation: {:?}", config);
+                ventilation.configure(config);
+                if let Some(co2) = rx_co2.try_get() {
+                    ventilation.update(co2, now_s());
+                }
+            }
+        }
+
+        let mut states = manual;
+        // 自动模式占用的引脚忽略手动写入，configure_ventilation 已检查引脚编号
+        if let Some(state) = ventilation
+            .config()
+            .pin
+            .and_then(|pin| states.get_mut(pin as usize))
+        {
+            *state = if ventilation.is_on() { 0xFF } else { 0 };
+        }
+        for (output, &state) in outputs.iter_mut().zip(states.iter()) {
+            output.set_level(if state != 0 { Level::High } else { Level::Low });
+        }
+        tx.send(states);
+    }
+}
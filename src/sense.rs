@@ -1,28 +1,81 @@
+pub mod sensor;
+
 use embassy_sync::{
     blocking_mutex::raw::ThreadModeRawMutex,
+    signal::Signal,
     watch::{DynReceiver, Watch},
 };
-use embassy_time::{Delay, Timer};
-use libscd::asynchronous::scd4x::Scd4x;
-use microbit_bsp::embassy_nrf::{
-    Peri, bind_interrupts,
-    peripherals::{P0_26, P1_00, TWISPI0},
-    twim::{self, Twim},
+use embassy_time::{Duration, Instant, Timer};
+use microbit_bsp::ble::MultiprotocolServiceLayer;
+use microbit_co2_core::{
+    climate::Climate,
+    filter::{FilterConfig, Filtered, ValidityFilter},
+    prediction,
+    sensor::{DieTemperature, SensorError, Variant, poll},
+    trend::{Averages, TrendTracker},
 };
-use static_cell::ConstStaticCell;
 
-// CO2消费者数量，分别是 display 和 ble
-const CO2_CONSUMERS: usize = 2;
+use crate::{air_quality::AirQualityConfig, bus::I2cBus, motion};
+
+pub use microbit_co2_core::sensor::Co2Sensor;
+
+// CO2消费者数量，分别是 display、ble、pins 和 air_quality
+const CO2_CONSUMERS: usize = 4;
 static CO2: Watch<ThreadModeRawMutex, u16, CO2_CONSUMERS> = Watch::new();
 
-// Temperature消费者数量，分别是 ble
-const TEMPERATURE_CONSUMERS: usize = 1;
+// Temperature消费者数量，分别是 display 和 ble
+const TEMPERATURE_CONSUMERS: usize = 2;
 static TEMPERATURE: Watch<ThreadModeRawMutex, i8, TEMPERATURE_CONSUMERS> = Watch::new();
 
-// Humidity消费者数量，分别是 ble
-const HUMIDITY_CONSUMERS: usize = 1;
+// Humidity消费者数量，分别是 display 和 ble
+const HUMIDITY_CONSUMERS: usize = 2;
 static HUMIDITY: Watch<ThreadModeRawMutex, u8, HUMIDITY_CONSUMERS> = Watch::new();
 
+// 露点、绝对湿度和舒适度消费者数量，分别是 display 和 ble
+const CLIMATE_CONSUMERS: usize = 2;
+static CLIMATE: Watch<ThreadModeRawMutex, Climate, CLIMATE_CONSUMERS> = Watch::new();
+
+// CO2 平均值和趋势消费者数量，分别是 display、ble 和 occupancy
+const AVERAGES_CONSUMERS: usize = 3;
+static AVERAGES: Watch<ThreadModeRawMutex, Averages, AVERAGES_CONSUMERS> = Watch::new();
+
+// 预计多少分钟后需要通风，消费者分别是 ble 通知和广播
+const VENTILATION_CONSUMERS: usize = 2;
+static VENTILATION: Watch<ThreadModeRawMutex, Option<u16>, VENTILATION_CONSUMERS> = Watch::new();
+
+// 芯片温度消费者数量，分别是 sense 和 ble
+const DIE_TEMPERATURE_CONSUMERS: usize = 2;
+static DIE_TEMPERATURE: Watch<ThreadModeRawMutex, f32, DIE_TEMPERATURE_CONSUMERS> = Watch::new();
+
+/// SCD4x temperature differs from the die estimate
+pub const HEALTH_TEMPERATURE_DIVERGENCE: u8 = 0x01;
+/// No SCD4x measurement recently, temperature comes from the die
+pub const HEALTH_TEMPERATURE_FALLBACK: u8 = 0x02;
+/// The sensor was just started and its readings are still being discarded
+pub const HEALTH_WARMING_UP: u8 = 0x04;
+/// The last CO2 reading was outside the sensor's plausible range and was rejected
+pub const HEALTH_OUT_OF_RANGE: u8 = 0x08;
+/// No CO2 sensor was found on the bus, it is probed again with a growing interval
+pub const HEALTH_SENSOR_MISSING: u8 = 0x10;
+
+// 传感器状态消费者数量，分别是 ble
+const HEALTH_CONSUMERS: usize = 1;
+static HEALTH: Watch<ThreadModeRawMutex, u8, HEALTH_CONSUMERS> = Watch::new();
+
+// 强制校准的目标浓度 (ppm)
+static CALIBRATE: Signal<ThreadModeRawMutex, u16> = Signal::new();
+
+pub fn calibrate(target_ppm: u16) {
+    CALIBRATE.signal(target_ppm);
+}
+
+// 需要通风的浓度 (ppm)，跟随空气质量配置中的 poor 阈值
+static VENTILATION_THRESHOLD: Signal<ThreadModeRawMutex, u16> = Signal::new();
+
+pub fn set_ventilation_threshold(ppm: u16) {
+    VENTILATION_THRESHOLD.signal(ppm);
+}
+
 pub fn get_co2_receiver() -> Option<DynReceiver<'static, u16>> {
     CO2.dyn_receiver()
 }
@@ -35,54 +88,235 @@ pub fn get_humidity_receiver() -> Option<DynReceiver<'static, u8>> {
     HUMIDITY.dyn_receiver()
 }
 
-#[embassy_executor::task]
-pub async fn sense_task(
-    twi: Peri<'static, TWISPI0>,
-    sda: Peri<'static, P1_00>,
-    scl: Peri<'static, P0_26>,
-) {
-    bind_interrupts!(struct Irqs {
-        TWISPI0 => twim::InterruptHandler<TWISPI0>;
-    });
-    static RAM_BUFFER: ConstStaticCell<[u8; 4]> = ConstStaticCell::new([0; 4]);
-    let i2c = Twim::new(twi, Irqs, sda, scl, Default::default(), RAM_BUFFER.take());
-
-    // 将控制器包装在互斥锁中
-    // let i2c_bus = Mutex::new(i2c, Default::default());
-    // 为每个驱动程序生成一个 I2C 设备
-    // let i2c_dev1 = I2cDevice::new(i2c_bus);
-
-    let mut scd = Scd4x::new(i2c, Delay);
+/// Dew point, absolute humidity and comfort from the sensor's own readings
+pub fn get_climate_receiver() -> Option<DynReceiver<'static, Climate>> {
+    CLIMATE.dyn_receiver()
+}
 
-    Timer::after_millis(30).await;
+/// Rolling CO2 averages and the trend, updated with every valid reading
+pub fn get_averages_receiver() -> Option<DynReceiver<'static, Averages>> {
+    AVERAGES.dyn_receiver()
+}
 
-    // When re-programming, the controller will be restarted,
-    // but not the sensor. We try to stop it in order to
-    // prevent the rest of the commands failing.
-    _ = scd.stop_periodic_measurement().await;
+/// Minutes until CO2 reaches the ventilation threshold at its recent rate of rise,
+/// None when it isn't rising towards it
+pub fn get_ventilation_receiver() -> Option<DynReceiver<'static, Option<u16>>> {
+    VENTILATION.dyn_receiver()
+}
 
-    defmt::info!("Sensor serial number: {:?}", scd.serial_number().await);
-    if let Err(e) = scd.start_periodic_measurement().await {
-        defmt::panic!("Failed to start periodic measurement: {:?}", e);
-    }
+/// Raw nRF52 die temperature in °C, which runs warmer than the room
+pub fn get_die_temperature_receiver() -> Option<DynReceiver<'static, f32>> {
+    DIE_TEMPERATURE.dyn_receiver()
+}
+
+/// Sensor health warnings, `HEALTH_*` bits
+pub fn get_health_receiver() -> Option<DynReceiver<'static, u8>> {
+    HEALTH.dyn_receiver()
+}
+
+// 超过该时长没有 SCD4x 读数时改用芯片温度
+const FALLBACK_AFTER: Duration = Duration::from_secs(30);
+
+// 连续读取失败多少次后重新启动测量
+const MAX_ERRORS: u32 = 5;
+
+// 没有找到传感器时重新探测的间隔，每次仍未找到时加倍
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_PROBE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[embassy_executor::task]
+pub async fn sense_task(bus: &'static I2cBus) {
+    Timer::after_millis(30).await;
+    let mut sensor = sensor::detect(bus).await;
+    let mut filter = ValidityFilter::new(filter_config(&sensor));
 
     let tx_co2 = CO2.sender();
+    let tx_averages = AVERAGES.sender();
+    let mut trend = TrendTracker::new();
+    let tx_ventilation = VENTILATION.sender();
+    let mut ventilation_ppm = AirQualityConfig::default().poor_ppm;
     let tx_temperature = TEMPERATURE.sender();
     let tx_humidity = HUMIDITY.sender();
+    let tx_climate = CLIMATE.sender();
+    let tx_health = HEALTH.sender();
+    let mut rx_die = DIE_TEMPERATURE.receiver().unwrap();
+    let mut die = DieTemperature::default();
+    let mut last_measurement = Instant::now();
+    let mut health = if sensor.variant().is_some() {
+        HEALTH_WARMING_UP
+    } else {
+        HEALTH_SENSOR_MISSING
+    };
+    tx_health.send(health);
+    let mut probe_interval = PROBE_INTERVAL;
+    let mut next_probe = Instant::now() + probe_interval;
+    let mut errors = 0;
+    #[cfg(feature = "capture")]
+    let mut captured_minute = 0;
     loop {
-        if scd.data_ready().await.unwrap() {
-            let m = scd.read_measurement().await.unwrap();
-            defmt::info!(
-                "CO2(二氧化碳): {}, Humidity(湿度): {}, Temperature(温度): {}",
-                m.co2,
-                m.humidity,
-                m.temperature
-            );
-            tx_co2.send(m.co2 as u16);
-            tx_temperature.send(m.temperature as i8);
-            tx_humidity.send(m.humidity as u8);
+        if let Some(ppm) = VENTILATION_THRESHOLD.try_take() {
+            ventilation_ppm = ppm;
+        }
+        if let Some(target_ppm) = CALIBRATE.try_take() {
+            match sensor.calibrate(target_ppm).await {
+                Ok(()) => defmt::info!("Forced recalibration to {} ppm done", target_ppm),
+                Err(e) => defmt::warn!("Forced recalibration failed: {:?}", e),
+            }
         }
 
+        match poll(&mut sensor).await {
+            Ok(Some(m)) => {
+                errors = 0;
+                last_measurement = Instant::now();
+                defmt::info!(
+                    "CO2(二氧化碳): {}, Humidity(湿度): {}, Temperature(温度): {}",
+                    m.co2,
+                    m.humidity,
+                    m.temperature
+                );
+                set_health(&mut health, HEALTH_TEMPERATURE_FALLBACK, false);
+                tx_temperature.send(m.temperature as i8);
+                if let Some(die_c) = rx_die.try_get() {
+                    let diverges = die.check(die_c, m.temperature);
+                    if diverges {
+                        defmt::warn!(
+                            "[sense] temperature {} differs from die estimate {}",
+                            m.temperature,
+                            die.ambient(die_c)
+                        );
+                    }
+                    set_health(&mut health, HEALTH_TEMPERATURE_DIVERGENCE, diverges);
+                }
+                tx_humidity.send(m.humidity as u8);
+                tx_climate.send(Climate::new(m.temperature, m.humidity));
+
+                // 携带途中的 CO2 读数不代表所在房间，丢弃
+                let filtered = if motion::is_carried() {
+                    defmt::info!("[sense] carried, CO2 reading suppressed");
+                    None
+                } else {
+                    Some(filter.push(m.co2))
+                };
+                match filtered {
+                    Some(Filtered::Valid(co2)) => {
+                        tx_co2.send(co2);
+                        let now_s = Instant::now().as_secs() as u32;
+                        let averages = trend.push(now_s, co2);
+                        // 录制夹具，每个整分钟一行 minute,co2_ppm
+                        #[cfg(feature = "capture")]
+                        if let Some(&ppm) = trend.history().first()
+                            && now_s / 60 != captured_minute
+                        {
+                            captured_minute = now_s / 60;
+                            defmt::println!("meeting_room.csv {},{}", captured_minute - 1, ppm);
+                        }
+                        tx_averages.send(averages);
+                        let minutes = prediction::minutes_until(
+                            averages.avg_1min,
+                            trend.history(),
+                            ventilation_ppm,
+                        );
+                        tx_ventilation.send_if_modified(|current| {
+                            let modified = *current != Some(minutes);
+                            *current = Some(minutes);
+                            modified
+                        });
+                    }
+                    Some(Filtered::WarmingUp) => {
+                        defmt::info!("[sense] warming up, reading discarded");
+                    }
+                    Some(Filtered::Invalid) => defmt::warn!("[sense] invalid CO2 reading"),
+                    Some(Filtered::OutOfRange(co2)) => {
+                        defmt::warn!("[sense] CO2 reading {} out of range", co2);
+                    }
+                    None => {}
+                }
+                if let Some(filtered) = filtered {
+                    let out_of_range = matches!(filtered, Filtered::OutOfRange(_));
+                    set_health(&mut health, HEALTH_OUT_OF_RANGE, out_of_range);
+                    let warming_up = matches!(filtered, Filtered::WarmingUp);
+                    set_health(&mut health, HEALTH_WARMING_UP, warming_up);
+                }
+            }
+            Ok(None) => {}
+            Err(SensorError::NotDetected) => {
+                // 没有传感器时不计错误，按退避间隔重新探测
+                if Instant::now() >= next_probe {
+                    sensor = sensor::detect(bus).await;
+                    probe_interval = if redetected(&sensor, &mut filter, &mut health) {
+                        PROBE_INTERVAL
+                    } else {
+                        (probe_interval * 2).min(MAX_PROBE_INTERVAL)
+                    };
+                    next_probe = Instant::now() + probe_interval;
+                }
+            }
+            Err(e) => {
+                errors += 1;
+                defmt::warn!("Failed to read measurement: {:?}", e);
+                if errors >= MAX_ERRORS {
+                    // 传感器可能掉电重启过或被更换，重新识别并开始测量
+                    errors = 0;
+                    _ = sensor.stop().await;
+                    sensor = sensor::detect(bus).await;
+                    redetected(&sensor, &mut filter, &mut health);
+                    probe_interval = PROBE_INTERVAL;
+                    next_probe = Instant::now() + probe_interval;
+                }
+            }
+        }
+
+        let fallback = rx_die
+            .try_changed()
+            .filter(|_| last_measurement.elapsed() >= FALLBACK_AFTER);
+        if let Some(die_c) = fallback {
+            tx_temperature.send(die.ambient(die_c) as i8);
+            set_health(&mut health, HEALTH_TEMPERATURE_FALLBACK, true);
+        }
+
+        tx_health.send_if_modified(|current| {
+            let modified = *current != Some(health);
+            *current = Some(health);
+            modified
+        });
+
         Timer::after_millis(1000).await;
     }
 }
+
+fn set_health(health: &mut u8, bit: u8, set: bool) {
+    let next = if set { *health | bit } else { *health & !bit };
+    if next != *health {
+        defmt::info!("[sense] health: {:#04x}", next);
+    }
+    *health = next;
+}
+
+// 重新识别传感器后重置过滤器和相关状态位，返回是否找到传感器
+fn redetected(sensor: &sensor::Detected, filter: &mut ValidityFilter, health: &mut u8) -> bool {
+    defmt::info!("[sense] sensor: {:?}", sensor.variant());
+    *filter = ValidityFilter::new(filter_config(sensor));
+    let found = sensor.variant().is_some();
+    set_health(health, HEALTH_SENSOR_MISSING, !found);
+    set_health(health, HEALTH_WARMING_UP, found);
+    found
+}
+
+fn filter_config(sensor: &sensor::Detected) -> FilterConfig {
+    sensor
+        .variant()
+        .map(Variant::filter_config)
+        .unwrap_or_default()
+}
+
+/// Reads the die temperature through the MPSL, which owns the TEMP peripheral
+#[embassy_executor::task]
+pub async fn die_temperature_task(_mpsl: &'static MultiprotocolServiceLayer<'static>) {
+    let tx = DIE_TEMPERATURE.sender();
+    loop {
+        // SAFETY: MPSL 已经初始化，这里借用确保它在整个任务期间有效
+        let quarter_degrees = unsafe { nrf_mpsl::raw::mpsl_temperature_get() };
+        tx.send(quarter_degrees as f32 / 4.0);
+        Timer::after_secs(5).await;
+    }
+}
@@ -0,0 +1,272 @@
+use embassy_time::{Delay, Timer};
+use embedded_hal_async::i2c::I2c;
+use libscd::asynchronous::{scd4x::Scd4x, scd30::Scd30};
+use microbit_co2_core::sensor::{Co2Sensor, Measurement, SensorError, Variant};
+
+use crate::bus::{Device, I2cBus};
+
+#[cfg(feature = "simulated-sensor")]
+use microbit_co2_core::sensor::SimulatedSensor;
+
+const SCD4X_ADDRESS: u8 = 0x62;
+const SCD30_ADDRESS: u8 = 0x61;
+
+fn driver_error<E: defmt::Format>(e: E) -> SensorError {
+    defmt::warn!("[sense] {:?}", e);
+    SensorError::Driver
+}
+
+pub struct Scd4xSensor {
+    scd: Scd4x<Device, Delay>,
+    variant: Variant,
+}
+
+impl Co2Sensor for Scd4xSensor {
+    fn variant(&self) -> Option<Variant> {
+        Some(self.variant)
+    }
+
+    async fn start(&mut self) -> Result<(), SensorError> {
+        self.scd
+            .start_periodic_measurement()
+            .await
+            .map_err(driver_error)
+    }
+
+    async fn stop(&mut self) -> Result<(), SensorError> {
+        self.scd
+            .stop_periodic_measurement()
+            .await
+            .map_err(driver_error)?;
+        // 停止后 500ms 内不响应其他命令
+        Timer::after_millis(500).await;
+        Ok(())
+    }
+
+    async fn data_ready(&mut self) -> Result<bool, SensorError> {
+        self.scd.data_ready().await.map_err(driver_error)
+    }
+
+    async fn read(&mut self) -> Result<Measurement, SensorError> {
+        let m = self.scd.read_measurement().await.map_err(driver_error)?;
+        Ok(Measurement {
+            co2: m.co2,
+            temperature: m.temperature,
+            humidity: m.humidity,
+        })
+    }
+
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError> {
+        // 强制校准前需要停止周期测量
+        self.stop().await?;
+        let result = self
+            .scd
+            .perform_forced_recalibration(target_ppm)
+            .await
+            .map(|_| ())
+            .map_err(driver_error);
+        self.start().await?;
+        result
+    }
+}
+
+pub struct Scd30Sensor {
+    scd: Scd30<Device, Delay>,
+}
+
+impl Co2Sensor for Scd30Sensor {
+    fn variant(&self) -> Option<Variant> {
+        Some(Variant::Scd30)
+    }
+
+    async fn start(&mut self) -> Result<(), SensorError> {
+        // 0 表示不做气压补偿
+        self.scd
+            .start_continuous_measurement(0)
+            .await
+            .map_err(driver_error)
+    }
+
+    async fn stop(&mut self) -> Result<(), SensorError> {
+        self.scd
+            .stop_continuous_measurement()
+            .await
+            .map_err(driver_error)
+    }
+
+    async fn data_ready(&mut self) -> Result<bool, SensorError> {
+        self.scd.data_ready().await.map_err(driver_error)
+    }
+
+    async fn read(&mut self) -> Result<Measurement, SensorError> {
+        let m = self.scd.read_measurement().await.map_err(driver_error)?;
+        Ok(Measurement {
+            co2: m.co2,
+            temperature: m.temperature,
+            humidity: m.humidity,
+        })
+    }
+
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError> {
+        // SCD30 在连续测量时直接写入校准值
+        self.scd
+            .set_forced_recalibration_value(target_ppm)
+            .await
+            .map_err(driver_error)
+    }
+}
+
+/// Whichever sensor `detect` found
+pub enum Detected {
+    Scd4x(Scd4xSensor),
+    Scd30(Scd30Sensor),
+    #[cfg(feature = "simulated-sensor")]
+    Simulated(SimulatedSensor),
+    Absent,
+}
+
+impl Co2Sensor for Detected {
+    fn variant(&self) -> Option<Variant> {
+        match self {
+            Self::Scd4x(sensor) => sensor.variant(),
+            Self::Scd30(sensor) => sensor.variant(),
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.variant(),
+            Self::Absent => None,
+        }
+    }
+
+    async fn start(&mut self) -> Result<(), SensorError> {
+        match self {
+            Self::Scd4x(sensor) => sensor.start().await,
+            Self::Scd30(sensor) => sensor.start().await,
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.start().await,
+            Self::Absent => Err(SensorError::NotDetected),
+        }
+    }
+
+    async fn stop(&mut self) -> Result<(), SensorError> {
+        match self {
+            Self::Scd4x(sensor) => sensor.stop().await,
+            Self::Scd30(sensor) => sensor.stop().await,
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.stop().await,
+            Self::Absent => Err(SensorError::NotDetected),
+        }
+    }
+
+    async fn data_ready(&mut self) -> Result<bool, SensorError> {
+        match self {
+            Self::Scd4x(sensor) => sensor.data_ready().await,
+            Self::Scd30(sensor) => sensor.data_ready().await,
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.data_ready().await,
+            Self::Absent => Err(SensorError::NotDetected),
+        }
+    }
+
+    async fn read(&mut self) -> Result<Measurement, SensorError> {
+        match self {
+            Self::Scd4x(sensor) => sensor.read().await,
+            Self::Scd30(sensor) => sensor.read().await,
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.read().await,
+            Self::Absent => Err(SensorError::NotDetected),
+        }
+    }
+
+    async fn calibrate(&mut self, target_ppm: u16) -> Result<(), SensorError> {
+        match self {
+            Self::Scd4x(sensor) => sensor.calibrate(target_ppm).await,
+            Self::Scd30(sensor) => sensor.calibrate(target_ppm).await,
+            #[cfg(feature = "simulated-sensor")]
+            Self::Simulated(sensor) => sensor.calibrate(target_ppm).await,
+            Self::Absent => Err(SensorError::NotDetected),
+        }
+    }
+}
+
+/// Probe the bus for a supported sensor and start it measuring
+pub async fn detect(bus: &'static I2cBus) -> Detected {
+    let mut probe = Device::new(bus, "co2-probe");
+    let mut sensor = if let Some(variant) = probe_scd4x(&mut probe).await {
+        let mut scd = Scd4x::new(Device::new(bus, "scd4x"), Delay);
+        defmt::info!(
+            "[sense] {:?} serial {:?}",
+            variant,
+            scd.serial_number().await
+        );
+        Detected::Scd4x(Scd4xSensor { scd, variant })
+    } else if probe_scd30(&mut probe).await {
+        defmt::info!("[sense] SCD30");
+        Detected::Scd30(Scd30Sensor {
+            scd: Scd30::new(Device::new(bus, "scd30"), Delay),
+        })
+    } else {
+        not_found()
+    };
+    if let Err(e) = sensor.start().await {
+        defmt::warn!("[sense] failed to start measurement: {:?}", e);
+    }
+    sensor
+}
+
+#[cfg(not(feature = "simulated-sensor"))]
+fn not_found() -> Detected {
+    defmt::warn!("[sense] no CO2 sensor found");
+    Detected::Absent
+}
+
+// 没有接传感器时用模拟数据驱动其余固件
+#[cfg(feature = "simulated-sensor")]
+fn not_found() -> Detected {
+    defmt::info!("[sense] no CO2 sensor found, using simulated readings");
+    Detected::Simulated(SimulatedSensor::default())
+}
+
+// 停止测量后读取型号，SCD40 之前的固件不支持该命令，按 SCD40 处理
+async fn probe_scd4x(i2c: &mut Device) -> Option<Variant> {
+    // 重新烧录时控制器会重启而传感器不会，先停止可能仍在进行的测量
+    i2c.write(SCD4X_ADDRESS, &[0x3F, 0x86]).await.ok()?;
+    Timer::after_millis(500).await;
+
+    let mut word = [0u8; 3];
+    let read = async {
+        i2c.write(SCD4X_ADDRESS, &[0x20, 0x2F]).await?;
+        Timer::after_millis(1).await;
+        i2c.read(SCD4X_ADDRESS, &mut word).await
+    };
+    if read.await.is_err() || crc8(&word[..2]) != word[2] {
+        return Some(Variant::Scd40);
+    }
+    Some(match word[0] >> 4 {
+        0b0001 => Variant::Scd41,
+        0b0101 => Variant::Scd43,
+        _ => Variant::Scd40,
+    })
+}
+
+// 读取固件版本确认 SCD30 存在
+async fn probe_scd30(i2c: &mut Device) -> bool {
+    let mut version = [0u8; 3];
+    let read = async {
+        i2c.write(SCD30_ADDRESS, &[0xD1, 0x00]).await?;
+        Timer::after_millis(3).await;
+        i2c.read(SCD30_ADDRESS, &mut version).await
+    };
+    read.await.is_ok() && crc8(&version[..2]) == version[2]
+}
+
+/// Sensirion CRC-8, polynomial 0x31 with initial value 0xFF
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0xFF, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
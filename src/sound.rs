@@ -0,0 +1,329 @@
+pub mod pcm;
+pub mod speaker;
+
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use embassy_futures::select::{Either3, select3};
+use embassy_sync::{
+    blocking_mutex::{Mutex, raw::ThreadModeRawMutex},
+    channel::Channel,
+    watch::{DynReceiver, Watch},
+};
+use embassy_time::{Duration, Instant, Timer};
+use microbit_bsp::embassy_nrf::{
+    Peri,
+    peripherals::{P0_00, PWM0},
+    pwm::{
+        self, Prescaler, SequenceConfig, SequencePwm, SimplePwm, SingleSequenceMode,
+        SingleSequencer,
+    },
+};
+
+use crate::{air_quality, clock};
+
+pub use microbit_co2_core::alarm::{Alarm, AlarmConfig, AlarmState};
+pub use pcm::{BufferEvent, JitterBuffer};
+pub use speaker::{Playback, SpeakerMode};
+
+// Prescaler::Div16 下 PWM 时钟为 1MHz
+const PWM_CLOCK_HZ: u32 = 1_000_000;
+// 约 250ms 的 8kHz PCM
+const PCM_BUFFER_SIZE: usize = 2048;
+// 16MHz 时钟下 250 个计数为一个 64kHz 的 PWM 周期，每个采样重复 8 个周期正好是 8kHz
+const PCM_TOP: u16 = 250;
+const PCM_REFRESH: u32 = 7;
+// 每个 DMA 序列的采样数，一个序列播放时填充另一个
+const PCM_CHUNK: usize = 256;
+const PCM_CHUNK_DURATION: Duration =
+    Duration::from_micros(PCM_CHUNK as u64 * 1_000_000 / pcm::SAMPLE_RATE_HZ as u64);
+// 没有新数据多少个采样后开始播放剩余数据，多少个采样后结束
+const PCM_FLUSH_SAMPLES: u32 = pcm::SAMPLE_RATE_HZ / 10;
+const PCM_STOP_SAMPLES: u32 = pcm::SAMPLE_RATE_HZ / 2;
+
+/// Values of the Thingy TSS speaker status characteristic
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum SpeakerStatus {
+    Finished = 0x00,
+    BufferWarning = 0x01,
+    BufferReady = 0x02,
+    PacketDisregarded = 0x10,
+    InvalidCommand = 0x11,
+    // 以下为 CO2 报警的扩展状态
+    AlarmSounding = 0x20,
+    AlarmSnoozed = 0x21,
+    AlarmSilenced = 0x22,
+}
+
+impl From<AlarmState> for SpeakerStatus {
+    fn from(value: AlarmState) -> Self {
+        match value {
+            AlarmState::Idle => Self::Finished,
+            AlarmState::Sounding => Self::AlarmSounding,
+            AlarmState::Snoozed => Self::AlarmSnoozed,
+            AlarmState::Silenced => Self::AlarmSilenced,
+        }
+    }
+}
+
+impl From<BufferEvent> for SpeakerStatus {
+    fn from(value: BufferEvent) -> Self {
+        match value {
+            BufferEvent::Warning => Self::BufferWarning,
+            BufferEvent::Ready => Self::BufferReady,
+            BufferEvent::Disregarded => Self::PacketDisregarded,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+enum Command {
+    Configure(AlarmConfig),
+    Snooze,
+    Play(Playback),
+}
+
+static COMMANDS: Channel<ThreadModeRawMutex, Command, 4> = Channel::new();
+static SOUNDING: AtomicBool = AtomicBool::new(false);
+static SPEAKER_MODE: AtomicU8 = AtomicU8::new(SpeakerMode::Frequency as u8);
+
+// BLE 写入，sound_task 播放
+static PCM: Mutex<ThreadModeRawMutex, RefCell<JitterBuffer<PCM_BUFFER_SIZE>>> =
+    Mutex::new(RefCell::new(JitterBuffer::new()));
+
+// 扬声器状态消费者数量，分别是 ble
+const STATUS_CONSUMERS: usize = 1;
+static STATUS: Watch<ThreadModeRawMutex, SpeakerStatus, STATUS_CONSUMERS> = Watch::new();
+
+fn send(command: Command) {
+    if COMMANDS.try_send(command).is_err() {
+        defmt::warn!("[sound] command dropped: {:?}", command);
+    }
+}
+
+pub fn configure_alarm(config: AlarmConfig) {
+    send(Command::Configure(config));
+}
+
+/// Snooze the alarm if it is sounding, returns whether the press was consumed
+pub fn snooze() -> bool {
+    if SOUNDING.load(Ordering::Relaxed) {
+        send(Command::Snooze);
+        true
+    } else {
+        false
+    }
+}
+
+pub fn set_speaker_mode(mode: SpeakerMode) {
+    SPEAKER_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Handle a TSS_SPEAKER write according to the current speaker mode
+pub fn speaker_write(data: &[u8]) {
+    let mode = SpeakerMode::try_from(SPEAKER_MODE.load(Ordering::Relaxed));
+    let playback = match mode {
+        Ok(SpeakerMode::Frequency) => Playback::parse_tone(data),
+        Ok(SpeakerMode::Sample) => Playback::parse_sample(data),
+        Ok(SpeakerMode::Pcm) => {
+            let (event, start) = PCM.lock(|pcm| {
+                let mut buffer = pcm.borrow_mut();
+                let idle = buffer.is_idle();
+                let event = buffer.push(data);
+                (event, idle && !buffer.is_idle())
+            });
+            if let Some(event) = event {
+                STATUS.sender().send(event.into());
+            }
+            if start && COMMANDS.try_send(Command::Play(Playback::Pcm)).is_err() {
+                // 没有任务读取缓冲区，回到空闲让下一个数据包重新启动播放
+                defmt::warn!("[sound] PCM playback dropped");
+                PCM.lock(|pcm| pcm.borrow_mut().stop());
+            }
+            return;
+        }
+        Err(_) => None,
+    };
+    match playback {
+        Some(playback) => send(Command::Play(playback)),
+        None => STATUS.sender().send(SpeakerStatus::InvalidCommand),
+    }
+}
+
+pub fn get_status_receiver() -> Option<DynReceiver<'static, SpeakerStatus>> {
+    STATUS.dyn_receiver()
+}
+
+#[embassy_executor::task]
+pub async fn sound_task(mut pwm0: Peri<'static, PWM0>, mut speaker: Peri<'static, P0_00>) {
+    let mut rx_air_quality = air_quality::get_status_receiver().unwrap();
+    let tx_status = STATUS.sender();
+    let mut alarm = Alarm::new(AlarmConfig::default());
+    loop {
+        let state = alarm.update(Instant::now().as_millis(), clock::minute_of_day());
+        SOUNDING.store(state == AlarmState::Sounding, Ordering::Relaxed);
+        tx_status.send_if_modified(|current| {
+            let status = Some(state.into());
+            let modified = *current != status;
+            *current = status;
+            modified
+        });
+
+        // PCM 播放改用 SequencePwm，两种驱动共用 PWM0，每轮重新创建
+        let mut pwm = SimplePwm::new_1ch(pwm0.reborrow(), speaker.reborrow());
+        let config = alarm.config();
+        let beep = async {
+            if state == AlarmState::Sounding {
+                start_tone(&mut pwm, config.frequency_hz, 100);
+                Timer::after_millis(config.on_ms as u64).await;
+                pwm.disable();
+                Timer::after_millis(config.off_ms as u64).await;
+            } else {
+                // 定期检查贪睡到期和静音时段
+                Timer::after_secs(1).await;
+            }
+        };
+        let mut pcm = false;
+        match select3(beep, rx_air_quality.changed(), COMMANDS.receive()).await {
+            Either3::First(_) => {}
+            Either3::Second(status) => alarm.observe(status),
+            Either3::Third(Command::Configure(config)) => {
+                defmt::info!("[sound] alarm: {:?}", config);
+                alarm.configure(config);
+            }
+            Either3::Third(Command::Snooze) => {
+                alarm.snooze(Instant::now().as_millis());
+            }
+            Either3::Third(Command::Play(Playback::Pcm)) => pcm = true,
+            Either3::Third(Command::Play(playback)) => {
+                pwm.disable();
+                play(&mut pwm, playback).await;
+                tx_status.send(SpeakerStatus::Finished);
+            }
+        }
+        pwm.disable();
+        drop(pwm);
+
+        if pcm {
+            play_pcm(pwm0.reborrow(), speaker.reborrow()).await;
+            tx_status.send(SpeakerStatus::Finished);
+        }
+    }
+}
+
+async fn play(pwm: &mut SimplePwm<'_>, playback: Playback) {
+    defmt::debug!("[sound] play {:?}", playback);
+    match playback {
+        Playback::Tone {
+            frequency_hz,
+            duration_ms,
+            volume,
+        } => {
+            start_tone(pwm, frequency_hz, volume);
+            Timer::after_millis(duration_ms as u64).await;
+        }
+        Playback::Sample(id) => {
+            for &(frequency_hz, duration_ms) in speaker::SAMPLES[id as usize] {
+                if frequency_hz == 0 {
+                    pwm.disable();
+                } else {
+                    start_tone(pwm, frequency_hz, 100);
+                }
+                Timer::after_millis(duration_ms as u64).await;
+            }
+        }
+        // PCM 需要 SequencePwm，由 sound_task 单独播放
+        Playback::Pcm => {}
+    }
+    pwm.disable();
+}
+
+/// Play the PCM jitter buffer as PWM duty cycles read by EasyDMA.
+///
+/// Two sequences alternate, the CPU fills one while the PWM plays the other,
+/// so the sample clock comes from the PWM rather than from task wake-ups.
+async fn play_pcm(pwm0: Peri<'_, PWM0>, speaker: Peri<'_, P0_00>) {
+    let mut config = pwm::Config::default();
+    config.prescaler = Prescaler::Div1;
+    config.max_duty = PCM_TOP;
+    let mut pwm = match SequencePwm::new_1ch(pwm0, speaker, config) {
+        Ok(pwm) => pwm,
+        Err(e) => {
+            defmt::warn!("[sound] PCM output failed: {:?}", defmt::Debug2Format(&e));
+            PCM.lock(|pcm| pcm.borrow_mut().stop());
+            return;
+        }
+    };
+
+    // EasyDMA 只能从 RAM 读取
+    let mut buffers = [[0u16; PCM_CHUNK]; 2];
+    let [mut playing, mut next] = buffers.each_mut();
+    let mut idle = 0;
+    if !fill_pcm(playing, &mut idle) {
+        return;
+    }
+    loop {
+        let mut sequence = SequenceConfig::default();
+        sequence.refresh = PCM_REFRESH;
+        let sequencer = SingleSequencer::new(&mut pwm, &playing[..], sequence);
+        let end = Instant::now() + PCM_CHUNK_DURATION;
+        if let Err(e) = sequencer.start(SingleSequenceMode::Times(1)) {
+            defmt::warn!("[sound] PCM sequence failed: {:?}", defmt::Debug2Format(&e));
+            PCM.lock(|pcm| pcm.borrow_mut().stop());
+            return;
+        }
+        let more = fill_pcm(next, &mut idle);
+        Timer::at(end).await;
+        drop(sequencer);
+        if !more {
+            return;
+        }
+        core::mem::swap(&mut playing, &mut next);
+    }
+}
+
+// 从缓冲区取出一个序列的采样，没有数据时输出静音；返回 false 表示播放结束
+fn fill_pcm(words: &mut [u16; PCM_CHUNK], idle: &mut u32) -> bool {
+    let (more, event) = PCM.lock(|pcm| {
+        let mut buffer = pcm.borrow_mut();
+        let mut last_event = None;
+        for word in words.iter_mut() {
+            if *idle == PCM_FLUSH_SAMPLES {
+                buffer.flush();
+            }
+            let (sample, event) = buffer.pop();
+            last_event = event.or(last_event);
+            *word = match sample {
+                Some(sample) => {
+                    *idle = 0;
+                    sample as u16 * PCM_TOP / u8::MAX as u16
+                }
+                None => {
+                    *idle += 1;
+                    PCM_TOP / 2
+                }
+            };
+        }
+        if *idle >= PCM_STOP_SAMPLES {
+            buffer.stop();
+            return (false, last_event);
+        }
+        (true, last_event)
+    });
+    if let Some(event) = event {
+        STATUS.sender().send(event.into());
+    }
+    more
+}
+
+// volume 0-100，对应 0-50% 占空比；COUNTERTOP 只有 15 位
+fn start_tone(pwm: &mut SimplePwm<'_>, frequency_hz: u16, volume: u8) {
+    let top = (PWM_CLOCK_HZ / frequency_hz.max(20) as u32).min(u16::MAX as u32 >> 1) as u16;
+    pwm.set_prescaler(Prescaler::Div16);
+    pwm.set_max_duty(top);
+    pwm.set_duty(0, (top as u32 * volume.min(100) as u32 / 200) as u16);
+    pwm.enable();
+}
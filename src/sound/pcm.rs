@@ -0,0 +1,117 @@
+use heapless::Deque;
+
+/// Sample rate of the Thingy 8-bit PCM stream
+pub const SAMPLE_RATE_HZ: u32 = 8000;
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum BufferEvent {
+    /// 缓冲区快满了，客户端应暂停发送
+    Warning,
+    /// 缓冲区已经回落，可以继续发送
+    Ready,
+    /// 缓冲区已满，整个数据包被丢弃
+    Disregarded,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 没有播放任务在读取
+    Idle,
+    /// 等待缓冲到预填充水平，或欠载后重新缓冲
+    Prefilling,
+    Playing,
+}
+
+/// Jitter buffer for PCM packets arriving over BLE.
+///
+/// Playback only starts once a quarter of the buffer is filled so that
+/// irregular connection events don't cause underruns. The buffer also tracks
+/// whether a playback task is reading from it at all.
+pub struct JitterBuffer<const N: usize> {
+    samples: Deque<u8, N>,
+    state: State,
+    warned: bool,
+}
+
+impl<const N: usize> Default for JitterBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> JitterBuffer<N> {
+    const PREFILL: usize = N / 4;
+    const WARNING: usize = N * 3 / 4;
+    const READY: usize = N / 2;
+
+    pub const fn new() -> Self {
+        Self {
+            samples: Deque::new(),
+            state: State::Idle,
+            warned: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// No playback is running, the next accepted packet starts one
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Idle
+    }
+
+    /// Playback ended or couldn't be started, buffered samples are dropped
+    pub fn stop(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Append a packet, packets that don't fit completely are dropped
+    pub fn push(&mut self, data: &[u8]) -> Option<BufferEvent> {
+        if self.samples.capacity() - self.samples.len() < data.len() {
+            return Some(BufferEvent::Disregarded);
+        }
+        for &sample in data {
+            // 空间已经检查过
+            self.samples.push_back(sample).ok();
+        }
+        if self.state == State::Idle {
+            self.state = State::Prefilling;
+        }
+        if self.samples.len() >= Self::PREFILL {
+            self.state = State::Playing;
+        }
+        if !self.warned && self.samples.len() >= Self::WARNING {
+            self.warned = true;
+            return Some(BufferEvent::Warning);
+        }
+        None
+    }
+
+    /// Start playing whatever is buffered, used when the stream ends before the prefill level
+    pub fn flush(&mut self) {
+        if self.state == State::Prefilling && !self.samples.is_empty() {
+            self.state = State::Playing;
+        }
+    }
+
+    /// Next sample, `None` while prefilling or after an underrun
+    pub fn pop(&mut self) -> (Option<u8>, Option<BufferEvent>) {
+        if self.state != State::Playing {
+            return (None, None);
+        }
+        let sample = self.samples.pop_front();
+        if sample.is_none() {
+            self.state = State::Prefilling;
+        }
+        if self.warned && self.samples.len() < Self::READY {
+            self.warned = false;
+            return (sample, Some(BufferEvent::Ready));
+        }
+        (sample, None)
+    }
+}
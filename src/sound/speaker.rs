@@ -0,0 +1,70 @@
+/// Speaker modes selected through the first byte of TSS_CONFIG
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum SpeakerMode {
+    Frequency = 1,
+    Pcm = 2,
+    Sample = 3,
+}
+
+impl TryFrom<u8> for SpeakerMode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Self::Frequency,
+            2 => Self::Pcm,
+            3 => Self::Sample,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Playback {
+    Tone {
+        frequency_hz: u16,
+        duration_ms: u16,
+        /// 0-100
+        volume: u8,
+    },
+    Sample(u8),
+    Pcm,
+}
+
+impl Playback {
+    /// Frequency mode write: frequency (u16), duration (u16) and volume (u8), little endian
+    pub fn parse_tone(data: &[u8]) -> Option<Self> {
+        match *data {
+            [f0, f1, d0, d1, volume] => Some(Self::Tone {
+                frequency_hz: u16::from_le_bytes([f0, f1]),
+                duration_ms: u16::from_le_bytes([d0, d1]),
+                volume: volume.min(100),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Sample mode write: a single sample ID
+    pub fn parse_sample(data: &[u8]) -> Option<Self> {
+        match *data {
+            [id] if (id as usize) < SAMPLES.len() => Some(Self::Sample(id)),
+            _ => None,
+        }
+    }
+}
+
+/// A note of a predefined sample, frequency 0 is a rest
+pub type Note = (u16, u16);
+
+/// Predefined samples, the same count as the Thingy firmware ships
+pub const SAMPLES: [&[Note]; 9] = [
+    &[(1319, 100), (1568, 100), (2093, 200)],
+    &[(2093, 100), (1568, 100), (1319, 200)],
+    &[(880, 80), (0, 40), (880, 80)],
+    &[(1760, 50)],
+    &[(440, 400)],
+    &[(523, 150), (659, 150), (784, 150), (1047, 300)],
+    &[(1047, 150), (784, 150), (659, 150), (523, 300)],
+    &[(2000, 100), (0, 100), (2000, 100), (0, 100), (2000, 100)],
+    &[(3000, 30), (0, 30), (3000, 30)],
+];
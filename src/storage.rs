@@ -0,0 +1,88 @@
+use core::ops::Range;
+
+use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, channel::Channel, mutex::Mutex};
+use heapless::Vec;
+use microbit_bsp::{ble::MultiprotocolServiceLayer, embassy_nrf::peripherals::NVMC};
+use nrf_mpsl::Flash;
+use sequential_storage::{cache::NoCache, map};
+use trouble_host::types::gatt_traits::FixedGattValue;
+
+// 与 memory.x 中预留的最后 8K 保持一致
+const FLASH_RANGE: Range<u32> = 0x7E000..0x80000;
+const MAX_VALUE_SIZE: usize = 32;
+
+/// Keys of the persisted settings, values are stored in their GATT representation
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+#[repr(u8)]
+pub enum Key {
+    AirQuality = 1,
+    Alarm = 2,
+    Compass = 3,
+    Occupancy = 4,
+}
+
+static FLASH: Mutex<ThreadModeRawMutex, Option<Flash<'static>>> = Mutex::new(None);
+static SAVE: Channel<ThreadModeRawMutex, (Key, Vec<u8, MAX_VALUE_SIZE>), 4> = Channel::new();
+
+pub async fn init(mpsl: &'static MultiprotocolServiceLayer<'static>) {
+    // SAFETY: NVMC 只在这里使用，microbit-bsp 没有导出它
+    let nvmc = unsafe { NVMC::steal() };
+    FLASH.lock().await.replace(Flash::take(mpsl, nvmc));
+}
+
+pub async fn load<T: FixedGattValue>(key: Key) -> Option<T> {
+    let mut flash = FLASH.lock().await;
+    let flash = flash.as_mut()?;
+    let mut buf = [0u8; MAX_VALUE_SIZE * 2];
+    match map::fetch_item::<u8, &[u8], _>(
+        flash,
+        FLASH_RANGE,
+        &mut NoCache::new(),
+        &mut buf,
+        &(key as u8),
+    )
+    .await
+    {
+        Ok(Some(data)) => T::from_gatt(data).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            defmt::warn!("[storage] failed to load {:?}: {:?}", key, defmt::Debug2Format(&e));
+            None
+        }
+    }
+}
+
+/// Queue a value to be written by `storage_task`
+pub fn save<T: FixedGattValue>(key: Key, value: &T) {
+    let Ok(data) = Vec::from_slice(value.as_gatt()) else {
+        defmt::warn!("[storage] {:?} too large", key);
+        return;
+    };
+    if SAVE.try_send((key, data)).is_err() {
+        defmt::warn!("[storage] save queue full, {:?} dropped", key);
+    }
+}
+
+#[embassy_executor::task]
+pub async fn storage_task() {
+    let mut buf = [0u8; MAX_VALUE_SIZE * 2];
+    loop {
+        let (key, data) = SAVE.receive().await;
+        let mut flash = FLASH.lock().await;
+        let Some(flash) = flash.as_mut() else {
+            continue;
+        };
+        if let Err(e) = map::store_item(
+            flash,
+            FLASH_RANGE,
+            &mut NoCache::new(),
+            &mut buf,
+            &(key as u8),
+            &data.as_slice(),
+        )
+        .await
+        {
+            defmt::warn!("[storage] failed to save {:?}: {:?}", key, defmt::Debug2Format(&e));
+        }
+    }
+}